@@ -0,0 +1,32 @@
+//! Decoding throughput of hot-path server messages: owned `ServerMessage`
+//! versus borrowed `ServerMessageRef`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use lasersell_stream_proto::{ServerMessage, ServerMessageRef};
+
+const TRADE_TICK: &str = r#"{"type":"trade_tick","position_id":7,"time_ms":1700000000123,"side":"buy","token_amount":50000000,"quote_amount":1200000000,"price_quote":24000,"maker":"4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T","tx_signature":"5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW"}"#;
+
+const PNL_UPDATE: &str = r#"{"type":"pnl_update","position_id":5,"profit_units":-12000,"proceeds_units":3400000,"server_time_ms":1700000000999,"token_price_quote":24000,"market_cap_quote":2000000000}"#;
+
+const LIQUIDITY_SNAPSHOT: &str = r#"{"type":"liquidity_snapshot","position_id":5,"bands":[{"slippage_bps":100,"max_tokens":5000,"coverage_pct":50.0},{"slippage_bps":500,"max_tokens":10000,"coverage_pct":100.0}],"liquidity_trend":"growing","server_time_ms":1700000000999}"#;
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    for (name, text) in [
+        ("trade_tick", TRADE_TICK),
+        ("pnl_update", PNL_UPDATE),
+        ("liquidity_snapshot", LIQUIDITY_SNAPSHOT),
+    ] {
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("owned", name), text, |b, text| {
+            b.iter(|| serde_json::from_str::<ServerMessage>(black_box(text)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("borrowed", name), text, |b, text| {
+            b.iter(|| ServerMessageRef::from_text(black_box(text)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,673 @@
+// Generated by lasersell-stream-proto. Do not edit.
+// Regenerate with `UPDATE_BINDINGS=1 cargo test --features typescript`.
+
+/** Protocol version described by these types. */
+export declare const PROTOCOL_VERSION: 2;
+
+/** Auto-buy configuration for a watched wallet. */
+export interface AutoBuyConfigMsg {
+  /** Amount to spend in SOL lamports when the watched wallet buys in a SOL market. */
+  amount_quote_units: number;
+  /**
+   * Amount to spend in USD1 base units when the watched wallet buys in a USD1 market.
+   * When `None`, USD1 markets are skipped for auto-buy.
+   */
+  amount_usd1_units?: number;
+  /** Which of the user's own wallets to execute the buy on. */
+  wallet_pubkey: Pubkey58;
+}
+
+export type Capability = "take_profit_levels" | "mirror_config" | "trade_ticks" | "liquidity_snapshots" | "position_strategy" | "request_ids" | "sequenced" | "batch" | "pnl_delta" | (string & {});
+
+/** Commands sent from client to server. */
+export type ClientMessage =
+  /**
+   * Optional handshake announcing the client's protocol version and capabilities.
+   *
+   * Sent before `Configure`. Servers reply with `HelloOk`.
+   */
+  | {
+    type: "hello";
+    /** Optional protocol features the client understands. */
+    capabilities?: Capability[];
+    /** Free-form client name and version for server logs. */
+    client_name?: string;
+    /** Encodings the client can read, most preferred first. Empty means JSON only. */
+    encodings?: Encoding[];
+    /** Protocol version spoken by the client. */
+    protocol_version: number;
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+  }
+  /** Keepalive ping from client. */
+  | {
+    type: "ping";
+    /** Client timestamp in Unix milliseconds. */
+    client_time_ms: number;
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+  }
+  /** Initial session configuration for wallets and strategy. */
+  | {
+    type: "configure";
+    /** Mirror trading hardening configuration. */
+    mirror_config?: MirrorConfigMsg;
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /** How the client will submit the signed transaction. */
+    send_mode: SendMode | null;
+    /** Strategy thresholds for the session. */
+    strategy: StrategyConfigMsg;
+    /** Priority fee tip in lamports (required for some send modes). */
+    tip_lamports: number | null;
+    /** Wallet pubkeys to monitor. Accepts legacy `wallet_pubkey` alias. */
+    wallet_pubkeys: Pubkey58[];
+    /** External wallets to watch for copy trading (tier 1+). */
+    watch_wallets?: WatchWalletEntryMsg[];
+  }
+  /** Update strategy thresholds for an active session. */
+  | {
+    type: "update_strategy";
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /** New strategy configuration. */
+    strategy: StrategyConfigMsg;
+  }
+  /** Request that a tracked position be closed. */
+  | {
+    type: "close_position";
+    /** Optional internal position identifier. */
+    position_id?: number;
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /** Optional token account key for lookup when ID is unknown. */
+    token_account?: Pubkey58;
+  }
+  /**
+   * Request an immediate exit signal and unsigned transaction.
+   *
+   * Also deserializes from the legacy `sell_now` message type.
+   */
+  | {
+    type: "request_exit_signal";
+    /** Optional internal position identifier. */
+    position_id?: number;
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /** Optional slippage tolerance, in basis points. */
+    slippage_bps?: number;
+    /** Optional token account key for lookup when ID is unknown. */
+    token_account?: Pubkey58;
+  }
+  /** Replace the set of monitored wallets for an active session. */
+  | {
+    type: "update_wallets";
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /** Full replacement list of wallet pubkeys. */
+    wallet_pubkeys: Pubkey58[];
+  }
+  /** Replace the set of watched external wallets for copy trading. */
+  | {
+    type: "update_watch_wallets";
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /** Full replacement list of watch wallet entries. */
+    watch_wallets: WatchWalletEntryMsg[];
+  }
+  /**
+   * Override the strategy for a single active position.
+   *
+   * The provided strategy fully replaces the session-level strategy for this
+   * position only. Other positions are unaffected.
+   */
+  | {
+    type: "update_position_strategy";
+    /** The position to override. */
+    position_id: number;
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /** Full strategy replacement for this position. */
+    strategy: StrategyConfigMsg;
+  }
+  /**
+   * Resume a sequenced session after a reconnect.
+   *
+   * The server replays events after `last_seq` if they are still buffered.
+   */
+  | {
+    type: "resume";
+    /** Highest sequence number the client has processed. */
+    last_seq: number;
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /** Session to resume. */
+    session_id: number;
+  }
+  /**
+   * Report the outcome of a mirror buy transaction back to the stream.
+   *
+   * Sent by the desktop app after signing and submitting (or failing to submit)
+   * a `MirrorBuySignal` transaction. Allows the stream to immediately clear
+   * pending state (dedup locks, pending tags) instead of waiting for the
+   * periodic cleanup sweep.
+   */
+  | {
+    type: "mirror_buy_result";
+    /** Token mint pubkey that the mirror buy targeted. */
+    mint: Pubkey58;
+    /** Optional client-chosen identifier echoed in the matching `Ack` or `Error`. */
+    request_id?: number;
+    /**
+     * True if the transaction was successfully submitted to the network.
+     * False if signing or submission failed.
+     */
+    success: boolean;
+  };
+
+export type Encoding = "json" | "msgpack" | "cbor" | (string & {});
+
+export type ErrorCode = "auth_failed" | "invalid_message" | "not_configured" | "invalid_strategy" | "unknown_position" | "hi_capacity_exceeded" | "max_positions_per_session_exceeded" | "max_wallets_per_session_exceeded" | "max_positions_per_wallet_exceeded" | "max_sessions_per_api_key_exceeded" | "max_watch_wallets_per_session_exceeded" | "tier_required" | "rate_limited" | "tx_build_failed" | "unavailable" | "internal_error" | (string & {});
+
+export type ExitReason = "tp" | "chained_tp" | "sl" | "trailing_stop" | "breakeven_trail" | "graduation" | "manual" | "mirror_sell" | "liquidity_guard" | "external_sell" | (string & {});
+
+/** Server-enforced per-session and per-key limits. */
+export interface LimitsMsg {
+  /** Max concurrent positions tracked at high priority. */
+  hi_capacity: number;
+  /** Max positions allowed in one session. */
+  max_positions_per_session: number;
+  /** Max tracked positions per wallet. */
+  max_positions_per_wallet: number;
+  /** Max simultaneous sessions per API key. */
+  max_sessions_per_api_key: number;
+  /** Max wallets accepted in one session. */
+  max_wallets_per_session: number;
+  /** Max external wallets that can be watched per session (copy trading). */
+  max_watch_wallets_per_session: number;
+  /** PnL push cadence in milliseconds. */
+  pnl_flush_ms: number;
+}
+
+export type LiquidityTrend = "growing" | "stable" | "draining" | (string & {});
+
+/**
+ * Market-specific context carried with position events.
+ *
+ * Exactly one optional context field should match `market_type`.
+ */
+export interface MarketContextMsg {
+  /** Market discriminator for the active context field. */
+  market_type: MarketTypeMsg;
+  /** Context for `meteora_damm_v2` markets. */
+  meteora_damm_v2?: MeteoraDammV2ContextMsg;
+  /** Context for `meteora_dbc` markets. */
+  meteora_dbc?: MeteoraDbcContextMsg;
+  /** Context for `pump_fun` markets. */
+  pumpfun?: PumpFunContextMsg;
+  /** Context for `pump_swap` markets. */
+  pumpswap?: PumpSwapContextMsg;
+  /** Context for `raydium_cpmm` markets. */
+  raydium_cpmm?: RaydiumCpmmContextMsg;
+  /** Context for `raydium_launchpad` markets. */
+  raydium_launchpad?: RaydiumLaunchpadContextMsg;
+}
+
+/** Supported market types for an opened position. */
+export type MarketTypeMsg = "pump_fun" | "pump_swap" | "meteora_dbc" | "meteora_damm_v2" | "raydium_launchpad" | "raydium_cpmm";
+
+/** Context payload for `MarketTypeMsg::MeteoraDammV2`. */
+export interface MeteoraDammV2ContextMsg {
+  /** Meteora DAMM v2 pool account. */
+  pool: Pubkey58;
+}
+
+/** Context payload for `MarketTypeMsg::MeteoraDbc`. */
+export interface MeteoraDbcContextMsg {
+  /** Meteora DBC config account. */
+  config: Pubkey58;
+  /** Meteora DBC pool account. */
+  pool: Pubkey58;
+  /** Quote mint used by the pool. */
+  quote_mint: Pubkey58;
+}
+
+/** Mirror trading hardening configuration sent during Configure. */
+export interface MirrorConfigMsg {
+  /** Slippage tolerance for mirror buys in basis points (0 = use default). */
+  buy_slippage_bps: number;
+  /** Cooldown between mirror buys in seconds (0 = no cooldown). */
+  cooldown_sec: number;
+  /** Max total SOL deployed across all active mirror positions (0.0 = unlimited). */
+  max_active_sol: number;
+  /** Auto-disable watched wallet after N consecutive losing mirror trades (None = disabled). */
+  max_consecutive_losses?: number;
+  /** Maximum price drift % from watched wallet's entry before skipping (None = disabled). */
+  max_entry_drift_pct?: number;
+  /** Max concurrent mirror positions per watched wallet (0 = unlimited). */
+  max_positions_per_wallet: number;
+  /** Minimum pool liquidity in SOL to allow a mirror buy (None = disabled). */
+  min_liquidity_sol?: number;
+  /** Skip tokens created/deployed by the watched wallet. */
+  skip_creator_tokens: boolean;
+}
+
+/** Base58 encoding of 32 bytes. */
+export type Pubkey58 = string;
+
+/**
+ * Context payload for `MarketTypeMsg::PumpFun`.
+ *
+ * This is currently empty and acts as an explicit marker.
+ */
+export type PumpFunContextMsg = Record<string, never>;
+
+/** Context payload for `MarketTypeMsg::PumpSwap`. */
+export interface PumpSwapContextMsg {
+  /** Optional PumpSwap global config account. */
+  global_config?: Pubkey58;
+  /** PumpSwap pool account. */
+  pool: Pubkey58;
+}
+
+export type QuoteAsset = "SOL" | "USD1" | (string & {});
+
+/** Context payload for `MarketTypeMsg::RaydiumCpmm`. */
+export interface RaydiumCpmmContextMsg {
+  /** Raydium CPMM config account. */
+  config: Pubkey58;
+  /** Raydium CPMM pool account. */
+  pool: Pubkey58;
+  /** Quote mint used by the pool. */
+  quote_mint: Pubkey58;
+  /** User's quote token account associated with the position. */
+  user_quote_account: Pubkey58;
+}
+
+/** Context payload for `MarketTypeMsg::RaydiumLaunchpad`. */
+export interface RaydiumLaunchpadContextMsg {
+  /** Raydium Launchpad config account. */
+  config: Pubkey58;
+  /** Raydium Launchpad platform account. */
+  platform: Pubkey58;
+  /** Raydium Launchpad pool account. */
+  pool: Pubkey58;
+  /** Quote mint used by the pool. */
+  quote_mint: Pubkey58;
+  /** User's quote token account associated with the position. */
+  user_quote_account: Pubkey58;
+}
+
+export type SendMode = "helius_sender" | "rpc" | "astralane" | (string & {});
+
+/** A server event tagged with its position in the session's event stream. */
+export interface ServerEnvelope {
+  /** The wrapped event. */
+  payload: ServerMessage;
+  /** Per-session sequence number, starting at 1. */
+  seq: number;
+  /** Session the sequence number belongs to. */
+  session_id: number;
+}
+
+/**
+ * A server frame that is either sequenced or a bare [`ServerMessage`].
+ *
+ * Bare messages are what servers without sequencing send.
+ */
+export type ServerFrame = ServerEnvelope | ServerMessage;
+
+/** Events and responses sent from server to client. */
+export type ServerMessage =
+  /** Successful handshake response with limits. */
+  | {
+    type: "hello_ok";
+    /** Optional protocol features enabled for this session. */
+    capabilities?: Capability[];
+    /** Encoding of the server's frames after this one. Absent means JSON. */
+    encoding?: Encoding;
+    /** Effective limits for the session/API key. */
+    limits: LimitsMsg;
+    /** Protocol version spoken for this session. Absent from legacy servers. */
+    protocol_version: number;
+    /** Server timestamp in Unix milliseconds. */
+    server_time_ms: number;
+    /** Assigned session identifier. */
+    session_id: number;
+  }
+  /** Keepalive pong from server. */
+  | {
+    type: "pong";
+    /** Server timestamp in Unix milliseconds. */
+    server_time_ms: number;
+  }
+  /** Acknowledges a client command that carried a `request_id`. */
+  | {
+    type: "ack";
+    /** The `request_id` of the acknowledged command. */
+    request_id: number;
+  }
+  /** Error response for invalid requests or runtime failures. */
+  | {
+    type: "error";
+    /** Stable machine-readable error code. */
+    code: ErrorCode;
+    /** Human-readable error message. */
+    message: string;
+    /** `request_id` of the command that caused the error, if any. */
+    request_id?: number;
+  }
+  /** Incremental PnL update for a position. */
+  | {
+    type: "pnl_update";
+    /** Current market cap in quote units. */
+    market_cap_quote?: number;
+    /** Internal position identifier. */
+    position_id: number;
+    /** Estimated proceeds in quote units. */
+    proceeds_units: number;
+    /** Profit/loss in quote units. */
+    profit_units: number;
+    /** Server timestamp in Unix milliseconds. */
+    server_time_ms: number;
+    /** Current token price in quote units. */
+    token_price_quote?: number;
+    /** True when this position belongs to a watched (copy-traded) wallet. */
+    watched?: boolean;
+  }
+  /**
+   * Compact PnL update carrying only the fields that changed.
+   *
+   * Only sent to clients that negotiated `Capability::PnlDelta`. The delta
+   * applies to the previous `PnlUpdate` (or delta) for the same position,
+   * identified by its `server_time_ms`. Use [`PnlDeltaDecoder`] to
+   * reconstruct full `PnlUpdate` messages.
+   */
+  | {
+    type: "pnl_delta";
+    /** `server_time_ms` of the update this delta applies to. */
+    base_server_time_ms: number;
+    /** New market cap in quote units, if changed. */
+    market_cap_quote?: number;
+    /** Internal position identifier. */
+    position_id: number;
+    /** New estimated proceeds in quote units, if changed. */
+    proceeds_units?: number;
+    /** New profit/loss in quote units, if changed. */
+    profit_units?: number;
+    /** Server timestamp in Unix milliseconds. */
+    server_time_ms: number;
+    /** New token price in quote units, if changed. */
+    token_price_quote?: number;
+  }
+  /** Liquidity snapshot for a position with slippage bands. */
+  | {
+    type: "liquidity_snapshot";
+    /** Slippage bands describing sellable amounts at each threshold. */
+    bands: SlippageBandMsg[];
+    /** Liquidity trend over recent snapshots. */
+    liquidity_trend: LiquidityTrend;
+    /** Internal position identifier. */
+    position_id: number;
+    /** Server timestamp in Unix milliseconds. */
+    server_time_ms: number;
+    /** True when this position belongs to a watched (copy-traded) wallet. */
+    watched?: boolean;
+  }
+  /** A trade (swap) observed on the pool for a tracked position. */
+  | {
+    type: "trade_tick";
+    /** Wallet that initiated the swap. */
+    maker?: Pubkey58;
+    /** Internal position identifier. */
+    position_id: number;
+    /** Price per token in lamports. */
+    price_quote: number;
+    /** Quote (SOL) amount traded in lamports. */
+    quote_amount: number;
+    /** Trade direction. */
+    side: TradeSide;
+    /** Unix timestamp in milliseconds when the trade was observed. */
+    time_ms: number;
+    /** Token amount traded in native units. */
+    token_amount: number;
+    /** Transaction signature (base58-encoded). */
+    tx_signature?: Signature58;
+    /** True when this position belongs to a watched (copy-traded) wallet. */
+    watched?: boolean;
+  }
+  /** Balance update for a tracked wallet/mint. */
+  | {
+    type: "balance_update";
+    /** Token mint pubkey. */
+    mint: Pubkey58;
+    /** Slot the balance snapshot came from. */
+    slot: number;
+    /** Optional token account pubkey. */
+    token_account?: Pubkey58;
+    /** Optional token program pubkey. */
+    token_program?: Pubkey58;
+    /** Token amount in native units. */
+    tokens: number;
+    /** Wallet pubkey the balance belongs to. */
+    wallet_pubkey: Pubkey58;
+  }
+  /** Notification that a new position has been opened. */
+  | {
+    type: "position_opened";
+    /** Entry cost in quote units. */
+    entry_quote_units: number;
+    /** Market cap in quote units at time of open. */
+    market_cap_quote?: number;
+    /** Optional market metadata for this position. */
+    market_context?: MarketContextMsg;
+    /** Token mint pubkey. */
+    mint: Pubkey58;
+    /** Identifier of the mirror source that triggered this position (e.g. watched wallet pubkey). */
+    mirror_source?: string;
+    /** Server timestamp when the position opened, in Unix milliseconds. */
+    opened_at_ms?: number;
+    /** Pool liquidity in quote units at time of open. */
+    pool_liquidity_quote?: number;
+    /** Internal position identifier. */
+    position_id: number;
+    /** Slot when the position opened. */
+    slot: number;
+    /** Token account pubkey. */
+    token_account: Pubkey58;
+    /** Token decimal places for display. */
+    token_decimals?: number;
+    /** Human-readable token name (e.g. "Lamppoli"). */
+    token_name?: string;
+    /** Token price in quote units at time of open. */
+    token_price_quote?: number;
+    /** Optional token program pubkey. */
+    token_program?: Pubkey58;
+    /** Token ticker symbol (e.g. "LAMP"). */
+    token_symbol?: string;
+    /** Position token amount in native units. */
+    tokens: number;
+    /** Wallet pubkey associated with the position. */
+    wallet_pubkey: Pubkey58;
+    /** True when this position belongs to a watched (copy-traded) wallet. */
+    watched?: boolean;
+  }
+  /** Notification that a position has been closed. */
+  | {
+    type: "position_closed";
+    /** Token mint pubkey. */
+    mint: Pubkey58;
+    /** Identifier of the mirror source that triggered this position (e.g. watched wallet pubkey). */
+    mirror_source?: string;
+    /** Internal position identifier. */
+    position_id: number;
+    /** Reason for the close event. */
+    reason: ExitReason;
+    /** Slot when the position closed. */
+    slot: number;
+    /** Optional token account pubkey. */
+    token_account?: Pubkey58;
+    /** Wallet pubkey associated with the position. */
+    wallet_pubkey: Pubkey58;
+    /** True when this position belongs to a watched (copy-traded) wallet. */
+    watched?: boolean;
+  }
+  /** Exit signal payload that includes an unsigned transaction. */
+  | {
+    type: "exit_signal_with_tx";
+    /** Which chained take-profit level fired (0-indexed). */
+    level_index?: number;
+    /** Optional market metadata for this position. */
+    market_context?: MarketContextMsg;
+    /** Token mint pubkey. */
+    mint: Pubkey58;
+    /** Identifier of the mirror source that triggered this position (e.g. watched wallet pubkey). */
+    mirror_source?: string;
+    /** Internal position identifier. */
+    position_id: number;
+    /** Position token amount in native units. */
+    position_tokens: number;
+    /** Profit/loss in quote units. */
+    profit_units: number;
+    /** Trigger reason for the exit. */
+    reason: ExitReason;
+    /** Tokens being sold (present for partial/chained sells). */
+    sell_tokens?: number;
+    /** Session identifier for correlation. */
+    session_id: number;
+    /** Optional token account pubkey. */
+    token_account?: Pubkey58;
+    /** Optional token program pubkey. */
+    token_program?: Pubkey58;
+    /** Trigger timestamp in Unix milliseconds. */
+    triggered_at_ms: number;
+    /** Base64-encoded unsigned transaction payload. */
+    unsigned_tx_b64: string;
+    /** Wallet pubkey associated with the position. */
+    wallet_pubkey: Pubkey58;
+    /** True when this position belongs to a watched (copy-traded) wallet. */
+    watched?: boolean;
+  }
+  /**
+   * Unsigned buy transaction triggered by a watched wallet's purchase.
+   *
+   * Sent when a watched wallet opens a position and the mirror buy passes
+   * all hardening checks. The client should sign and submit the transaction,
+   * then send a `MirrorBuyResult` message back to report the outcome.
+   */
+  | {
+    type: "mirror_buy_signal";
+    /** Amount to spend in quote units (lamports for SOL, base units for USD1). */
+    amount_quote_units: number;
+    /** Quote asset used for the buy. */
+    input: QuoteAsset;
+    /** Optional market metadata. */
+    market_context?: MarketContextMsg;
+    /** Token mint pubkey being bought. */
+    mint: Pubkey58;
+    /** Transaction send mode. */
+    send_mode?: SendMode;
+    /** Session identifier for correlation. */
+    session_id: number;
+    /** Slippage tolerance in basis points. */
+    slippage_bps: number;
+    /** Priority fee tip in lamports. */
+    tip_lamports?: number;
+    /** Base64-encoded unsigned buy transaction. */
+    unsigned_tx_b64: string;
+    /** User's own wallet that will execute the buy. */
+    user_wallet: Pubkey58;
+    /** Watched wallet that triggered this mirror buy. */
+    watched_wallet: Pubkey58;
+  }
+  /** Notification that a mirror buy could not be executed. */
+  | {
+    type: "mirror_buy_failed";
+    /** Token mint pubkey that was targeted. */
+    mint: Pubkey58;
+    /** Human-readable reason for the failure. */
+    reason: string;
+    /** Watched wallet that triggered the failed buy. */
+    watched_wallet: Pubkey58;
+  }
+  /** Notification that a watched wallet has been auto-disabled. */
+  | {
+    type: "mirror_wallet_auto_disabled";
+    /** Number of consecutive losses that triggered the disable. */
+    loss_count: number;
+    /** Reason for auto-disable (e.g. "consecutive_losses"). */
+    reason: string;
+    /** Watched wallet pubkey that was disabled. */
+    watched_wallet: Pubkey58;
+  }
+  /**
+   * Several messages delivered in one frame.
+   *
+   * Only sent to clients that negotiated `Capability::Batch`. Use
+   * [`ServerMessage::into_messages`] or [`flatten_batches`] to process a
+   * batch as if its messages had arrived one by one.
+   */
+  | {
+    type: "batch";
+    /** Batched messages, in the order they would have been sent. */
+    messages: ServerMessage[];
+  };
+
+/** Base58 encoding of 64 bytes. */
+export type Signature58 = string;
+
+/** A slippage band describing the max sellable tokens at a given slippage. */
+export interface SlippageBandMsg {
+  /** Coverage as a percentage of the full position (0..100). */
+  coverage_pct: number;
+  /** Maximum tokens sellable within this slippage band. */
+  max_tokens: number;
+  /** Slippage threshold in basis points. */
+  slippage_bps: number;
+}
+
+/** Client-side strategy thresholds used for automated exits. */
+export interface StrategyConfigMsg {
+  /** Move stop loss to breakeven once profit reaches this percentage (0 = disabled). */
+  breakeven_trail_pct: number;
+  /** Scale down chained sells when pool liquidity is thin and redistribute overflow. */
+  liquidity_guard: boolean;
+  /** Automatically sell when a token graduates to a new DEX. */
+  sell_on_graduation: boolean;
+  /** Stop-loss percentage. */
+  stop_loss_pct: number;
+  /** Multi-level take-profit chain. When non-empty, overrides `target_profit_pct`. */
+  take_profit_levels?: TakeProfitLevelMsg[];
+  /** Target take-profit percentage (legacy single-level; ignored when `take_profit_levels` is non-empty). */
+  target_profit_pct: number;
+  /** Trailing stop percentage (locks in profits as price rises). */
+  trailing_stop_pct: number;
+}
+
+/** A single take-profit level in a chained sell strategy. */
+export interface TakeProfitLevelMsg {
+  /** PnL percentage threshold to trigger this level. */
+  profit_pct: number;
+  /** Percentage of remaining position to sell when triggered. */
+  sell_pct: number;
+  /** New trailing stop percentage after this level fires (0 = no change). */
+  trailing_stop_pct: number;
+}
+
+export type TradeSide = "buy" | "sell" | (string & {});
+
+/** A single watched wallet entry with optional auto-buy mirror config. */
+export interface WatchWalletEntryMsg {
+  /**
+   * Optional auto-buy config. When set, the stream triggers a buy on
+   * the user's own wallet whenever the watched wallet opens a position.
+   */
+  auto_buy?: AutoBuyConfigMsg;
+  /**
+   * When true, the stream also mirrors the watched wallet's sells by
+   * triggering a full exit on the user's corresponding mirror position.
+   */
+  mirror_sell: boolean;
+  /** Solana pubkey of the external wallet to watch. */
+  pubkey: Pubkey58;
+}
@@ -0,0 +1,121 @@
+//! Flattening of `ServerMessage::Batch` frames.
+//!
+//! Servers that negotiated [`Capability::Batch`](crate::Capability::Batch)
+//! may coalesce bursts of events into one `Batch` frame. The iterators here
+//! expand batches (including nested ones) in order, so consumers written for
+//! one message per frame keep working unchanged.
+
+use std::vec;
+
+use crate::ServerMessage;
+
+/// Iterator over the messages of one frame, with batches expanded.
+///
+/// Returned by [`ServerMessage::into_messages`].
+#[derive(Debug, Clone)]
+pub struct IntoMessages {
+    stack: Vec<vec::IntoIter<ServerMessage>>,
+}
+
+impl Iterator for IntoMessages {
+    type Item = ServerMessage;
+
+    fn next(&mut self) -> Option<ServerMessage> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some(ServerMessage::Batch { messages }) => self.stack.push(messages.into_iter()),
+                Some(msg) => return Some(msg),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl ServerMessage {
+    /// Consumes the message and yields it, or the contents of a batch.
+    pub fn into_messages(self) -> IntoMessages {
+        IntoMessages {
+            stack: vec![vec![self].into_iter()],
+        }
+    }
+
+    /// True for `Batch` frames.
+    pub fn is_batch(&self) -> bool {
+        matches!(self, ServerMessage::Batch { .. })
+    }
+}
+
+/// Iterator adapter returned by [`flatten_batches`].
+#[derive(Debug, Clone)]
+pub struct FlattenBatches<I> {
+    frames: I,
+    current: Option<IntoMessages>,
+}
+
+impl<I: Iterator<Item = ServerMessage>> Iterator for FlattenBatches<I> {
+    type Item = ServerMessage;
+
+    fn next(&mut self) -> Option<ServerMessage> {
+        loop {
+            if let Some(msg) = self.current.as_mut().and_then(Iterator::next) {
+                return Some(msg);
+            }
+            self.current = Some(self.frames.next()?.into_messages());
+        }
+    }
+}
+
+/// Expands every `Batch` in a stream of frames into its messages.
+pub fn flatten_batches<I>(frames: I) -> FlattenBatches<I::IntoIter>
+where
+    I: IntoIterator<Item = ServerMessage>,
+{
+    FlattenBatches {
+        frames: frames.into_iter(),
+        current: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pong(server_time_ms: u64) -> ServerMessage {
+        ServerMessage::Pong { server_time_ms }
+    }
+
+    fn batch(messages: Vec<ServerMessage>) -> ServerMessage {
+        ServerMessage::Batch { messages }
+    }
+
+    #[test]
+    fn batch_round_trips_json() {
+        let msg = batch(vec![pong(1), ServerMessage::Ack { request_id: 2 }]);
+        let text = msg.to_text().expect("serialize");
+        assert_eq!(
+            text,
+            r#"{"type":"batch","messages":[{"type":"pong","server_time_ms":1},{"type":"ack","request_id":2}]}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ServerMessage>(&text).expect("deserialize"),
+            msg
+        );
+    }
+
+    #[test]
+    fn flattens_nested_and_empty_batches_in_order() {
+        let frames = vec![
+            pong(1),
+            batch(vec![pong(2), batch(vec![pong(3), pong(4)]), batch(vec![])]),
+            batch(vec![]),
+            pong(5),
+        ];
+        let flat: Vec<ServerMessage> = flatten_batches(frames).collect();
+        assert_eq!(flat, (1..=5).map(pong).collect::<Vec<_>>());
+
+        assert_eq!(pong(9).into_messages().collect::<Vec<_>>(), vec![pong(9)]);
+        assert!(batch(vec![]).is_batch());
+    }
+}
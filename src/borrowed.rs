@@ -0,0 +1,331 @@
+//! Borrowed views of the high-frequency server messages.
+//!
+//! [`ServerMessageRef`] decodes `PnlUpdate`, `LiquiditySnapshot` and
+//! `TradeTick` straight into structs whose strings borrow from the input, so
+//! the hot path allocates nothing per message (a `LiquiditySnapshot` still
+//! allocates its `bands` vector). Every other message type is decoded into an
+//! owned [`ServerMessage`].
+//!
+//! Servers write the `type` tag first, which lets the tag be read in a single
+//! pass. Frames with the tag elsewhere are still accepted but take a slower,
+//! allocating path.
+
+use std::borrow::Cow;
+use std::fmt;
+
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+
+use crate::{LiquidityTrend, Pubkey58, ServerMessage, Signature58, SlippageBandMsg, TradeSide};
+
+/// Borrowed counterpart of `ServerMessage::PnlUpdate`.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+pub struct PnlUpdateRef {
+    /// Position identifier.
+    pub position_id: u64,
+    /// Profit in quote units (can be negative).
+    pub profit_units: i64,
+    /// Proceeds in quote units.
+    pub proceeds_units: u64,
+    /// Server timestamp in Unix milliseconds.
+    pub server_time_ms: u64,
+    /// Current token price in quote units.
+    #[serde(default)]
+    pub token_price_quote: Option<u64>,
+    /// Current market cap in quote units.
+    #[serde(default)]
+    pub market_cap_quote: Option<u64>,
+    /// True for positions opened by a watched wallet.
+    #[serde(default)]
+    pub watched: bool,
+}
+
+/// Borrowed counterpart of `ServerMessage::LiquiditySnapshot`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct LiquiditySnapshotRef {
+    /// Position identifier.
+    pub position_id: u64,
+    /// Sellable token amounts per slippage tier.
+    pub bands: Vec<SlippageBandMsg>,
+    /// Direction pool liquidity is moving.
+    pub liquidity_trend: LiquidityTrend,
+    /// Server timestamp in Unix milliseconds.
+    pub server_time_ms: u64,
+    /// True for positions opened by a watched wallet.
+    #[serde(default)]
+    pub watched: bool,
+}
+
+/// Borrowed counterpart of `ServerMessage::TradeTick`.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct TradeTickRef<'a> {
+    /// Position identifier.
+    pub position_id: u64,
+    /// Trade timestamp in Unix milliseconds.
+    pub time_ms: u64,
+    /// Buy or sell.
+    pub side: TradeSide,
+    /// Tokens traded.
+    pub token_amount: u64,
+    /// Quote units traded.
+    pub quote_amount: u64,
+    /// Price in quote units.
+    pub price_quote: u64,
+    /// Base58 pubkey of the trader.
+    #[serde(default, borrow, deserialize_with = "optional_str")]
+    pub maker: Option<Cow<'a, str>>,
+    /// Base58 transaction signature.
+    #[serde(default, borrow, deserialize_with = "optional_str")]
+    pub tx_signature: Option<Cow<'a, str>>,
+    /// True for positions opened by a watched wallet.
+    #[serde(default)]
+    pub watched: bool,
+}
+
+/// A server message decoded without allocating for the hot variants.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerMessageRef<'a> {
+    /// Borrowed `PnlUpdate`.
+    PnlUpdate(PnlUpdateRef),
+    /// Borrowed `LiquiditySnapshot`.
+    LiquiditySnapshot(LiquiditySnapshotRef),
+    /// Borrowed `TradeTick`.
+    TradeTick(TradeTickRef<'a>),
+    /// Any other message, decoded as owned.
+    Other(Box<ServerMessage>),
+}
+
+impl<'a> ServerMessageRef<'a> {
+    /// Parses a JSON string, borrowing from it where possible.
+    pub fn from_text(text: &'a str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    /// Converts into the owned [`ServerMessage`].
+    pub fn into_owned(self) -> ServerMessage {
+        match self {
+            ServerMessageRef::PnlUpdate(msg) => ServerMessage::PnlUpdate {
+                position_id: msg.position_id,
+                profit_units: msg.profit_units,
+                proceeds_units: msg.proceeds_units,
+                server_time_ms: msg.server_time_ms,
+                token_price_quote: msg.token_price_quote,
+                market_cap_quote: msg.market_cap_quote,
+                watched: msg.watched,
+            },
+            ServerMessageRef::LiquiditySnapshot(msg) => ServerMessage::LiquiditySnapshot {
+                position_id: msg.position_id,
+                bands: msg.bands,
+                liquidity_trend: msg.liquidity_trend,
+                server_time_ms: msg.server_time_ms,
+                watched: msg.watched,
+            },
+            ServerMessageRef::TradeTick(msg) => ServerMessage::TradeTick {
+                position_id: msg.position_id,
+                time_ms: msg.time_ms,
+                side: msg.side,
+                token_amount: msg.token_amount,
+                quote_amount: msg.quote_amount,
+                price_quote: msg.price_quote,
+                // Already checked against the key policy during decoding.
+                maker: msg.maker.map(Pubkey58::new_unchecked),
+                tx_signature: msg.tx_signature.map(Signature58::new_unchecked),
+                watched: msg.watched,
+            },
+            ServerMessageRef::Other(msg) => *msg,
+        }
+    }
+}
+
+impl From<ServerMessageRef<'_>> for ServerMessage {
+    fn from(msg: ServerMessageRef<'_>) -> Self {
+        msg.into_owned()
+    }
+}
+
+/// A string that borrows from the input when the format allows it.
+struct Str<'a>(Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for Str<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StrVisitor;
+
+        impl<'de> Visitor<'de> for StrVisitor {
+            type Value = Str<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, value: &'de str) -> Result<Str<'de>, E> {
+                Ok(Str(Cow::Borrowed(value)))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Str<'de>, E> {
+                Ok(Str(Cow::Owned(value.to_string())))
+            }
+
+            fn visit_string<E: de::Error>(self, value: String) -> Result<Str<'de>, E> {
+                Ok(Str(Cow::Owned(value)))
+            }
+        }
+
+        deserializer.deserialize_str(StrVisitor)
+    }
+}
+
+fn optional_str<'de, D>(deserializer: D) -> Result<Option<Cow<'de, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Str<'de>>::deserialize(deserializer)?.map(|s| s.0))
+}
+
+fn is_hot(tag: &str) -> bool {
+    matches!(tag, "pnl_update" | "liquidity_snapshot" | "trade_tick")
+}
+
+/// Decodes the body of a hot message whose tag is already known.
+fn decode_hot<'de, D>(tag: &str, deserializer: D) -> Result<ServerMessageRef<'de>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match tag {
+        "pnl_update" => PnlUpdateRef::deserialize(deserializer).map(ServerMessageRef::PnlUpdate),
+        "liquidity_snapshot" => {
+            LiquiditySnapshotRef::deserialize(deserializer).map(ServerMessageRef::LiquiditySnapshot)
+        }
+        "trade_tick" => {
+            let tick = TradeTickRef::deserialize(deserializer)?;
+            if let Some(maker) = &tick.maker {
+                Pubkey58::check_wire(maker).map_err(de::Error::custom)?;
+            }
+            if let Some(signature) = &tick.tx_signature {
+                Signature58::check_wire(signature).map_err(de::Error::custom)?;
+            }
+            Ok(ServerMessageRef::TradeTick(tick))
+        }
+        _ => unreachable!("decode_hot called with {tag}"),
+    }
+}
+
+/// Decodes a message that was buffered into a JSON object.
+fn decode_buffered<'de, E: de::Error>(
+    fields: serde_json::Map<String, serde_json::Value>,
+) -> Result<ServerMessageRef<'de>, E> {
+    let tag = match fields.get("type") {
+        Some(serde_json::Value::String(tag)) if is_hot(tag) => Some(tag.clone()),
+        _ => None,
+    };
+    let value = serde_json::Value::Object(fields);
+    match tag {
+        Some(tag) => decode_hot(&tag, value).map_err(E::custom),
+        None => ServerMessage::deserialize(value)
+            .map(|msg| ServerMessageRef::Other(Box::new(msg)))
+            .map_err(E::custom),
+    }
+}
+
+impl<'de> Deserialize<'de> for ServerMessageRef<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MessageVisitor;
+
+        impl<'de> Visitor<'de> for MessageVisitor {
+            type Value = ServerMessageRef<'de>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a server message")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let Some(Str(first)) = map.next_key::<Str<'de>>()? else {
+                    return Err(de::Error::missing_field("type"));
+                };
+                if first == "type" {
+                    let Str(tag) = map.next_value::<Str<'de>>()?;
+                    if is_hot(&tag) {
+                        return decode_hot(&tag, MapAccessDeserializer::new(map));
+                    }
+                    let mut fields = serde_json::Map::deserialize(MapAccessDeserializer::new(map))?;
+                    fields.insert("type".to_string(), tag.into_owned().into());
+                    return decode_buffered(fields);
+                }
+
+                let mut fields = serde_json::Map::new();
+                fields.insert(first.into_owned(), map.next_value()?);
+                while let Some((key, value)) = map.next_entry()? {
+                    fields.insert(key, value);
+                }
+                decode_buffered(fields)
+            }
+        }
+
+        deserializer.deserialize_map(MessageVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn hot_messages_borrow_and_convert_to_owned() {
+        for msg in fixtures::server_messages() {
+            let text = msg.to_text().expect("serialize");
+            let decoded = ServerMessageRef::from_text(&text).expect("deserialize");
+            match &decoded {
+                ServerMessageRef::TradeTick(tick) => {
+                    assert!(matches!(tick.maker, Some(Cow::Borrowed(_))));
+                    assert!(matches!(tick.tx_signature, Some(Cow::Borrowed(_))));
+                }
+                ServerMessageRef::PnlUpdate(_) | ServerMessageRef::LiquiditySnapshot(_) => {}
+                ServerMessageRef::Other(other) => assert!(!matches!(
+                    **other,
+                    ServerMessage::PnlUpdate { .. }
+                        | ServerMessage::LiquiditySnapshot { .. }
+                        | ServerMessage::TradeTick { .. }
+                )),
+            }
+            assert_eq!(decoded.into_owned(), msg);
+        }
+    }
+
+    #[test]
+    fn accepts_tag_after_other_fields() {
+        let raw = r#"{"position_id":1,"time_ms":2,"side":"buy","token_amount":3,
+            "quote_amount":4,"price_quote":5,"maker":"11111111111111111111111111111111",
+            "type":"trade_tick"}"#;
+        let decoded = ServerMessageRef::from_text(raw).expect("deserialize");
+        let ServerMessageRef::TradeTick(tick) = &decoded else {
+            panic!("expected TradeTick");
+        };
+        assert_eq!(tick.side, TradeSide::Buy);
+        assert_eq!(
+            decoded.into_owned(),
+            serde_json::from_str::<ServerMessage>(raw).expect("owned")
+        );
+
+        let raw = r#"{"server_time_ms":5,"type":"pong"}"#;
+        assert_eq!(
+            ServerMessageRef::from_text(raw).expect("deserialize"),
+            ServerMessageRef::Other(Box::new(ServerMessage::Pong { server_time_ms: 5 }))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_type_and_missing_tag() {
+        assert!(ServerMessageRef::from_text(r#"{"type":"mystery","x":1}"#).is_err());
+        assert!(ServerMessageRef::from_text(r#"{"position_id":1}"#).is_err());
+        assert!(ServerMessageRef::from_text("{}").is_err());
+    }
+
+    #[cfg(feature = "strict-keys")]
+    #[test]
+    fn strict_keys_rejects_invalid_maker() {
+        let raw = r#"{"type":"trade_tick","position_id":1,"time_ms":2,"side":"buy",
+            "token_amount":3,"quote_amount":4,"price_quote":5,"maker":"not-a-key"}"#;
+        assert!(ServerMessageRef::from_text(raw).is_err());
+    }
+}
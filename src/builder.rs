@@ -0,0 +1,357 @@
+//! Fluent builders for strategies and `Configure` commands.
+//!
+//! Builders start from defaults, so code using them keeps compiling when new
+//! optional fields are added to the wire types. `build()` runs
+//! [`StrategyConfigMsg::validate`] and rejects error-severity issues.
+//!
+//! ```
+//! use lasersell_stream_proto::{ClientMessage, Pubkey58, StrategyConfigMsg};
+//!
+//! let strategy = StrategyConfigMsg::builder()
+//!     .stop_loss(5.0)
+//!     .take_profit_level(50.0, 30.0)
+//!     .take_profit_level(100.0, 50.0)
+//!     .trailing(10.0)
+//!     .build()?;
+//!
+//! let msg = ClientMessage::configure()
+//!     .wallet(Pubkey58::from_bytes(&[1; 32]))
+//!     .strategy(strategy)
+//!     .build()?;
+//!
+//! let json = serde_json::to_string(&msg)?;
+//! assert_eq!(ClientMessage::from_text(&json)?, msg);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt;
+
+use crate::strategy::StrategyIssue;
+use crate::{
+    ClientMessage, MirrorConfigMsg, Pubkey58, SendMode, StrategyConfigMsg, TakeProfitLevelMsg,
+    WatchWalletEntryMsg,
+};
+
+/// Error returned by builder `build()` methods.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// The strategy has error-severity validation issues.
+    InvalidStrategy(Vec<StrategyIssue>),
+    /// `Configure` needs at least one wallet.
+    NoWallets,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::InvalidStrategy(issues) => {
+                f.write_str("invalid strategy")?;
+                for (i, issue) in issues.iter().enumerate() {
+                    f.write_str(if i == 0 { ": " } else { "; " })?;
+                    write!(f, "{issue}")?;
+                }
+                Ok(())
+            }
+            BuildError::NoWallets => f.write_str("at least one wallet is required"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+fn check_strategy(strategy: &StrategyConfigMsg) -> Result<(), BuildError> {
+    let errors: Vec<StrategyIssue> = strategy
+        .validate()
+        .into_iter()
+        .filter(StrategyIssue::is_error)
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(BuildError::InvalidStrategy(errors))
+    }
+}
+
+/// Builder for [`StrategyConfigMsg`].
+#[derive(Debug, Clone, Default)]
+pub struct StrategyBuilder {
+    strategy: StrategyConfigMsg,
+}
+
+impl StrategyBuilder {
+    /// Creates a builder with every rule disabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the legacy single-level take profit (`target_profit_pct`).
+    pub fn take_profit(mut self, pct: f64) -> Self {
+        self.strategy.target_profit_pct = pct;
+        self
+    }
+
+    /// Sets the stop-loss percentage.
+    pub fn stop_loss(mut self, pct: f64) -> Self {
+        self.strategy.stop_loss_pct = pct;
+        self
+    }
+
+    /// Sets the trailing stop percentage.
+    pub fn trailing(mut self, pct: f64) -> Self {
+        self.strategy.trailing_stop_pct = pct;
+        self
+    }
+
+    /// Appends a take-profit level that keeps the current trailing stop.
+    pub fn take_profit_level(self, profit_pct: f64, sell_pct: f64) -> Self {
+        self.take_profit_level_with_trailing(profit_pct, sell_pct, 0.0)
+    }
+
+    /// Appends a take-profit level that switches the trailing stop when it fires.
+    pub fn take_profit_level_with_trailing(
+        mut self,
+        profit_pct: f64,
+        sell_pct: f64,
+        trailing_stop_pct: f64,
+    ) -> Self {
+        self.strategy.take_profit_levels.push(TakeProfitLevelMsg {
+            profit_pct,
+            sell_pct,
+            trailing_stop_pct,
+        });
+        self
+    }
+
+    /// Moves the stop loss to breakeven once profit reaches `pct`.
+    pub fn breakeven_trail(mut self, pct: f64) -> Self {
+        self.strategy.breakeven_trail_pct = pct;
+        self
+    }
+
+    /// Sells automatically when the token graduates.
+    pub fn sell_on_graduation(mut self, enabled: bool) -> Self {
+        self.strategy.sell_on_graduation = enabled;
+        self
+    }
+
+    /// Scales down chained sells when pool liquidity is thin.
+    pub fn liquidity_guard(mut self, enabled: bool) -> Self {
+        self.strategy.liquidity_guard = enabled;
+        self
+    }
+
+    /// Validates and returns the strategy.
+    pub fn build(self) -> Result<StrategyConfigMsg, BuildError> {
+        check_strategy(&self.strategy)?;
+        Ok(self.strategy)
+    }
+}
+
+impl StrategyConfigMsg {
+    /// Starts building a strategy.
+    pub fn builder() -> StrategyBuilder {
+        StrategyBuilder::new()
+    }
+}
+
+/// Builder for [`ClientMessage::Configure`].
+#[derive(Debug, Clone, Default)]
+pub struct ConfigureBuilder {
+    wallet_pubkeys: Vec<Pubkey58>,
+    strategy: StrategyConfigMsg,
+    send_mode: Option<SendMode>,
+    tip_lamports: Option<u64>,
+    watch_wallets: Vec<WatchWalletEntryMsg>,
+    mirror_config: Option<MirrorConfigMsg>,
+    request_id: Option<u64>,
+}
+
+impl ConfigureBuilder {
+    /// Creates a builder with no wallets and a default strategy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a wallet to monitor.
+    pub fn wallet(mut self, pubkey: Pubkey58) -> Self {
+        self.wallet_pubkeys.push(pubkey);
+        self
+    }
+
+    /// Adds several wallets to monitor.
+    pub fn wallets(mut self, pubkeys: impl IntoIterator<Item = Pubkey58>) -> Self {
+        self.wallet_pubkeys.extend(pubkeys);
+        self
+    }
+
+    /// Sets the session strategy.
+    pub fn strategy(mut self, strategy: StrategyConfigMsg) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets how signed transactions will be submitted.
+    pub fn send_mode(mut self, send_mode: SendMode) -> Self {
+        self.send_mode = Some(send_mode);
+        self
+    }
+
+    /// Sets the priority fee tip in lamports.
+    pub fn tip_lamports(mut self, lamports: u64) -> Self {
+        self.tip_lamports = Some(lamports);
+        self
+    }
+
+    /// Adds an external wallet to watch for copy trading.
+    pub fn watch_wallet(mut self, entry: WatchWalletEntryMsg) -> Self {
+        self.watch_wallets.push(entry);
+        self
+    }
+
+    /// Sets the mirror trading hardening configuration.
+    pub fn mirror_config(mut self, config: MirrorConfigMsg) -> Self {
+        self.mirror_config = Some(config);
+        self
+    }
+
+    /// Sets the correlation identifier.
+    pub fn request_id(mut self, id: u64) -> Self {
+        self.request_id = Some(id);
+        self
+    }
+
+    /// Validates and returns the `Configure` command.
+    pub fn build(self) -> Result<ClientMessage, BuildError> {
+        if self.wallet_pubkeys.is_empty() {
+            return Err(BuildError::NoWallets);
+        }
+        check_strategy(&self.strategy)?;
+        Ok(ClientMessage::Configure {
+            wallet_pubkeys: self.wallet_pubkeys,
+            strategy: self.strategy,
+            send_mode: self.send_mode,
+            tip_lamports: self.tip_lamports,
+            watch_wallets: self.watch_wallets,
+            mirror_config: self.mirror_config,
+            request_id: self.request_id,
+        })
+    }
+}
+
+impl ClientMessage {
+    /// Starts building a `Configure` command.
+    pub fn configure() -> ConfigureBuilder {
+        ConfigureBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::StrategyIssueKind;
+
+    #[test]
+    fn strategy_builder_sets_fields() {
+        let strategy = StrategyConfigMsg::builder()
+            .stop_loss(5.0)
+            .trailing(10.0)
+            .take_profit_level_with_trailing(50.0, 30.0, 5.0)
+            .take_profit_level(100.0, 30.0)
+            .breakeven_trail(20.0)
+            .liquidity_guard(true)
+            .build()
+            .expect("valid");
+        assert_eq!(
+            strategy,
+            StrategyConfigMsg {
+                target_profit_pct: 0.0,
+                stop_loss_pct: 5.0,
+                trailing_stop_pct: 10.0,
+                sell_on_graduation: false,
+                take_profit_levels: vec![
+                    TakeProfitLevelMsg {
+                        profit_pct: 50.0,
+                        sell_pct: 30.0,
+                        trailing_stop_pct: 5.0,
+                    },
+                    TakeProfitLevelMsg {
+                        profit_pct: 100.0,
+                        sell_pct: 30.0,
+                        trailing_stop_pct: 0.0,
+                    },
+                ],
+                liquidity_guard: true,
+                breakeven_trail_pct: 20.0,
+            }
+        );
+    }
+
+    #[test]
+    fn strategy_builder_rejects_errors_but_not_warnings() {
+        let err = StrategyConfigMsg::builder()
+            .take_profit_level(50.0, 30.0)
+            .take_profit_level(20.0, 30.0)
+            .build()
+            .unwrap_err();
+        let BuildError::InvalidStrategy(issues) = err else {
+            panic!("expected InvalidStrategy");
+        };
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, StrategyIssueKind::NonMonotonicLevels);
+
+        assert!(StrategyConfigMsg::builder()
+            .take_profit(10.0)
+            .take_profit_level(50.0, 30.0)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn configure_builder_builds_command() {
+        let wallet = Pubkey58::from_bytes(&[1; 32]);
+        let msg = ClientMessage::configure()
+            .wallet(wallet.clone())
+            .strategy(StrategyConfigMsg::builder().stop_loss(5.0).build().unwrap())
+            .send_mode(SendMode::HeliusSender)
+            .tip_lamports(1_000)
+            .request_id(7)
+            .build()
+            .expect("valid");
+        let ClientMessage::Configure {
+            wallet_pubkeys,
+            send_mode,
+            tip_lamports,
+            request_id,
+            ..
+        } = msg
+        else {
+            panic!("expected Configure");
+        };
+        assert_eq!(wallet_pubkeys, vec![wallet]);
+        assert_eq!(send_mode, Some(SendMode::HeliusSender));
+        assert_eq!(tip_lamports, Some(1_000));
+        assert_eq!(request_id, Some(7));
+    }
+
+    #[test]
+    fn configure_builder_requires_wallet_and_valid_strategy() {
+        assert_eq!(
+            ClientMessage::configure().build(),
+            Err(BuildError::NoWallets)
+        );
+
+        let invalid = StrategyConfigMsg {
+            stop_loss_pct: -1.0,
+            ..Default::default()
+        };
+        let err = ClientMessage::configure()
+            .wallet(Pubkey58::from_bytes(&[1; 32]))
+            .strategy(invalid)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid strategy: stop_loss_pct: must not be negative"
+        );
+    }
+}
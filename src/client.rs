@@ -0,0 +1,724 @@
+//! Async WebSocket client for the stream API (`client` feature).
+//!
+//! [`StreamClient::connect`] opens the socket, sends the optional `Hello` and
+//! the `Configure` command, and waits for `HelloOk`. A background task then
+//! runs the `Ping`/`Pong` keepalive and forwards server messages, which the
+//! client yields as a [`Stream`]. Commands are sent through the client or a
+//! cloned [`CommandSender`]. [`ReconnectingClient`] adds reconnection with
+//! configuration replay on top.
+//!
+//! Frames are JSON text until `HelloOk`. If it names a binary encoding, frames
+//! in both directions use that encoding from then on; an encoding this build
+//! does not support fails the handshake.
+//!
+//! ```no_run
+//! use futures_util::StreamExt;
+//! use lasersell_stream_proto::client::{ClientConfig, StreamClient};
+//! # async fn run(configure: lasersell_stream_proto::ClientMessage) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut client = StreamClient::connect(ClientConfig::new("wss://example.com/stream", configure)).await?;
+//! while let Some(msg) = client.next().await {
+//!     println!("{msg:?}");
+//! }
+//! if let Some(err) = client.error() {
+//!     eprintln!("disconnected: {err}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::{SinkExt, Stream, StreamExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant, MissedTickBehavior};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::clock::now_ms;
+use crate::{ClientMessage, ClockSync, Encoding, ErrorCode, ServerMessage};
+
+mod reconnect;
+
+pub use reconnect::{ReconnectPolicy, ReconnectingClient, ReplayState, StreamEvent};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Server messages buffered between the socket task and the client.
+const EVENT_BUFFER: usize = 256;
+
+/// Connection settings for [`StreamClient`].
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// WebSocket URL of the stream endpoint.
+    pub url: String,
+    /// Extra HTTP headers for the upgrade request, e.g. the API key.
+    pub headers: Vec<(String, String)>,
+    /// Handshake sent before `Configure`, if any.
+    pub hello: Option<ClientMessage>,
+    /// Session configuration sent after connecting.
+    pub configure: ClientMessage,
+    /// Maximum time from opening the socket to receiving `HelloOk`.
+    pub handshake_timeout: Duration,
+    /// Interval between keepalive `Ping`s.
+    pub keepalive_interval: Duration,
+    /// How long a `Ping` may go unanswered before the connection is dropped.
+    pub keepalive_timeout: Duration,
+}
+
+impl ClientConfig {
+    /// Creates a configuration with default timeouts and no `Hello`.
+    pub fn new(url: impl Into<String>, configure: ClientMessage) -> Self {
+        Self {
+            url: url.into(),
+            headers: Vec::new(),
+            hello: None,
+            configure,
+            handshake_timeout: Duration::from_secs(10),
+            keepalive_interval: Duration::from_secs(15),
+            keepalive_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Adds an HTTP header to the upgrade request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sends `hello` before `Configure`.
+    pub fn hello(mut self, hello: ClientMessage) -> Self {
+        self.hello = Some(hello);
+        self
+    }
+}
+
+/// Error returned by [`StreamClient`] and [`CommandSender`].
+#[derive(Debug)]
+pub enum ClientError {
+    /// WebSocket or HTTP failure.
+    WebSocket(Box<tungstenite::Error>),
+    /// A frame could not be encoded or decoded.
+    Json(serde_json::Error),
+    /// A binary frame could not be encoded or decoded.
+    Codec(Box<dyn std::error::Error + Send + Sync>),
+    /// `HelloOk` named an encoding this build cannot read or write.
+    UnsupportedEncoding(Encoding),
+    /// The server answered the handshake with an error.
+    Rejected {
+        /// Error code sent by the server.
+        code: ErrorCode,
+        /// Error message sent by the server.
+        message: String,
+    },
+    /// No `HelloOk` arrived within the handshake timeout.
+    HandshakeTimeout,
+    /// A `Ping` went unanswered for longer than the keepalive timeout.
+    KeepaliveTimeout,
+    /// The connection is closed.
+    Closed,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::WebSocket(err) => write!(f, "websocket error: {err}"),
+            ClientError::Json(err) => write!(f, "invalid frame: {err}"),
+            ClientError::Codec(err) => write!(f, "invalid binary frame: {err}"),
+            ClientError::UnsupportedEncoding(encoding) => {
+                write!(f, "server chose unsupported encoding {encoding}")
+            }
+            ClientError::Rejected { code, message } => {
+                write!(f, "handshake rejected: {code}: {message}")
+            }
+            ClientError::HandshakeTimeout => f.write_str("timed out waiting for hello_ok"),
+            ClientError::KeepaliveTimeout => f.write_str("server stopped answering pings"),
+            ClientError::Closed => f.write_str("connection closed"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::WebSocket(err) => Some(err.as_ref()),
+            ClientError::Json(err) => Some(err),
+            ClientError::Codec(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<tungstenite::Error> for ClientError {
+    fn from(err: tungstenite::Error) -> Self {
+        ClientError::WebSocket(Box::new(err))
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(err: serde_json::Error) -> Self {
+        ClientError::Json(err)
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    Send(Box<ClientMessage>),
+    Close,
+}
+
+/// Cloneable handle for sending commands on a [`StreamClient`]'s connection.
+#[derive(Debug, Clone)]
+pub struct CommandSender {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl CommandSender {
+    /// Queues a command for sending.
+    ///
+    /// Fails with [`ClientError::Closed`] once the connection has ended.
+    pub fn send(&self, msg: ClientMessage) -> Result<(), ClientError> {
+        self.commands
+            .send(Command::Send(Box::new(msg)))
+            .map_err(|_| ClientError::Closed)
+    }
+
+    /// True once the connection has ended.
+    pub fn is_closed(&self) -> bool {
+        self.commands.is_closed()
+    }
+}
+
+/// A configured connection to the stream API.
+///
+/// Yields every server message, starting with `HelloOk`. The stream ends when
+/// the connection does; [`StreamClient::error`] then tells why.
+#[derive(Debug)]
+pub struct StreamClient {
+    hello_ok: ServerMessage,
+    pending: VecDeque<ServerMessage>,
+    events: mpsc::Receiver<Result<ServerMessage, ClientError>>,
+    sender: CommandSender,
+    task: JoinHandle<()>,
+    error: Option<ClientError>,
+    clock: SharedClock,
+}
+
+impl StreamClient {
+    /// Connects, configures the session and waits for `HelloOk`.
+    pub async fn connect(config: ClientConfig) -> Result<Self, ClientError> {
+        Self::connect_with_clock(config, SharedClock::default()).await
+    }
+
+    /// Connects, feeding keepalive exchanges into `clock`.
+    pub(crate) async fn connect_with_clock(
+        config: ClientConfig,
+        clock: SharedClock,
+    ) -> Result<Self, ClientError> {
+        let mut request = config.url.as_str().into_client_request()?;
+        for (name, value) in &config.headers {
+            let name = HeaderName::try_from(name.as_str()).map_err(http_error)?;
+            let value = HeaderValue::try_from(value.as_str()).map_err(http_error)?;
+            request.headers_mut().append(name, value);
+        }
+
+        let deadline = Instant::now() + config.handshake_timeout;
+        let (mut socket, _) = time::timeout_at(deadline, tokio_tungstenite::connect_async(request))
+            .await
+            .map_err(|_| ClientError::HandshakeTimeout)??;
+        if let Some(hello) = &config.hello {
+            send(&mut socket, hello, &Encoding::Json).await?;
+        }
+        send(&mut socket, &config.configure, &Encoding::Json).await?;
+
+        let mut pending = VecDeque::new();
+        let hello_ok = loop {
+            let frame = time::timeout_at(deadline, socket.next())
+                .await
+                .map_err(|_| ClientError::HandshakeTimeout)?;
+            let Some(msg) = decode(frame.ok_or(ClientError::Closed)??, &Encoding::Json)? else {
+                continue;
+            };
+            match msg {
+                ServerMessage::HelloOk { .. } => break msg,
+                // Any error before `HelloOk` answers the handshake, even
+                // one the server does not end the session over.
+                ServerMessage::Error { code, message, .. } => {
+                    return Err(ClientError::Rejected { code, message });
+                }
+                msg => pending.push_back(msg),
+            }
+        };
+        pending.push_back(hello_ok.clone());
+        let encoding = match &hello_ok {
+            ServerMessage::HelloOk {
+                encoding: Some(encoding),
+                ..
+            } => encoding.clone(),
+            _ => Encoding::Json,
+        };
+        if !encoding.is_supported() {
+            return Err(ClientError::UnsupportedEncoding(encoding));
+        }
+
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        let (events_tx, events_rx) = mpsc::channel(EVENT_BUFFER);
+        let task = tokio::spawn(run(
+            socket,
+            config,
+            encoding,
+            commands_rx,
+            events_tx,
+            clock.clone(),
+        ));
+        Ok(Self {
+            hello_ok,
+            pending,
+            events: events_rx,
+            sender: CommandSender {
+                commands: commands_tx,
+            },
+            task,
+            error: None,
+            clock,
+        })
+    }
+
+    /// The `HelloOk` that completed the handshake.
+    pub fn hello_ok(&self) -> &ServerMessage {
+        &self.hello_ok
+    }
+
+    /// Session identifier assigned by the server.
+    pub fn session_id(&self) -> u64 {
+        match self.hello_ok {
+            ServerMessage::HelloOk { session_id, .. } => session_id,
+            _ => unreachable!("hello_ok is always a HelloOk"),
+        }
+    }
+
+    /// Queues a command for sending.
+    pub fn send(&self, msg: ClientMessage) -> Result<(), ClientError> {
+        self.sender.send(msg)
+    }
+
+    /// Returns a cloneable handle for sending commands from other tasks.
+    pub fn sender(&self) -> CommandSender {
+        self.sender.clone()
+    }
+
+    /// Why the connection ended, once the stream has returned `None`.
+    ///
+    /// `None` after a clean close by either side.
+    pub fn error(&self) -> Option<&ClientError> {
+        self.error.as_ref()
+    }
+
+    /// Latency and clock offset estimated from the keepalive so far.
+    pub fn clock(&self) -> ClockSync {
+        lock(&self.clock).clone()
+    }
+
+    /// Sends a close frame and waits for the connection to shut down.
+    pub async fn close(mut self) {
+        let _ = self.sender.commands.send(Command::Close);
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Stream for StreamClient {
+    type Item = ServerMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ServerMessage>> {
+        if let Some(msg) = self.pending.pop_front() {
+            return Poll::Ready(Some(msg));
+        }
+        match self.events.poll_recv(cx) {
+            Poll::Ready(Some(Ok(msg))) => Poll::Ready(Some(msg)),
+            Poll::Ready(Some(Err(err))) => {
+                self.error = Some(err);
+                Poll::Ready(None)
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for StreamClient {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn http_error(err: impl Into<tungstenite::http::Error>) -> ClientError {
+    tungstenite::Error::HttpFormat(err.into()).into()
+}
+
+/// Clock estimate shared with the socket task.
+pub(crate) type SharedClock = Arc<Mutex<ClockSync>>;
+
+pub(crate) fn lock(clock: &SharedClock) -> MutexGuard<'_, ClockSync> {
+    clock.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Sends a command as JSON text or, once a binary encoding is negotiated, as
+/// a binary frame.
+async fn send(
+    socket: &mut Socket,
+    msg: &ClientMessage,
+    encoding: &Encoding,
+) -> Result<(), ClientError> {
+    let frame = match encoding {
+        #[cfg(feature = "msgpack")]
+        Encoding::MessagePack => Message::binary(msg.encode_binary().map_err(codec_error)?),
+        #[cfg(feature = "cbor")]
+        Encoding::Cbor => Message::binary(msg.encode_cbor().map_err(codec_error)?),
+        _ => Message::text(serde_json::to_string(msg)?),
+    };
+    socket.send(frame).await?;
+    Ok(())
+}
+
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+fn codec_error(err: impl std::error::Error + Send + Sync + 'static) -> ClientError {
+    ClientError::Codec(Box::new(err))
+}
+
+/// Decodes a data frame; control frames yield `None` and close frames end
+/// the connection.
+///
+/// Text frames are always JSON; binary frames use the negotiated encoding.
+fn decode(frame: Message, encoding: &Encoding) -> Result<Option<ServerMessage>, ClientError> {
+    match frame {
+        Message::Text(text) => Ok(Some(serde_json::from_str(text.as_str())?)),
+        Message::Binary(bytes) => match encoding {
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => ServerMessage::decode_binary(&bytes)
+                .map(Some)
+                .map_err(codec_error),
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => ServerMessage::decode_cbor(&bytes)
+                .map(Some)
+                .map_err(codec_error),
+            _ => Ok(Some(serde_json::from_slice(&bytes)?)),
+        },
+        Message::Close(_) => Err(ClientError::Closed),
+        Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => Ok(None),
+    }
+}
+
+/// Socket task: forwards frames, sends commands and runs the keepalive.
+async fn run(
+    mut socket: Socket,
+    config: ClientConfig,
+    encoding: Encoding,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::Sender<Result<ServerMessage, ClientError>>,
+    clock: SharedClock,
+) {
+    let result = drive(
+        &mut socket,
+        &config,
+        &encoding,
+        &mut commands,
+        &events,
+        &clock,
+    )
+    .await;
+    match result {
+        Ok(()) | Err(ClientError::Closed) => {
+            let _ = socket.close(None).await;
+        }
+        Err(err) => {
+            let _ = events.send(Err(err)).await;
+        }
+    }
+}
+
+async fn drive(
+    socket: &mut Socket,
+    config: &ClientConfig,
+    encoding: &Encoding,
+    commands: &mut mpsc::UnboundedReceiver<Command>,
+    events: &mpsc::Sender<Result<ServerMessage, ClientError>>,
+    clock: &SharedClock,
+) -> Result<(), ClientError> {
+    let mut keepalive = time::interval_at(
+        Instant::now() + config.keepalive_interval,
+        config.keepalive_interval,
+    );
+    keepalive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // Send time of the oldest unanswered ping.
+    let mut awaiting_pong: Option<Instant> = None;
+
+    loop {
+        tokio::select! {
+            frame = socket.next() => {
+                let Some(frame) = frame else {
+                    return Ok(());
+                };
+                let msg = match decode(frame?, encoding) {
+                    Ok(Some(msg)) => msg,
+                    Ok(None) => continue,
+                    Err(ClientError::Closed) => return Ok(()),
+                    Err(err) => return Err(err),
+                };
+                if let ServerMessage::Pong { server_time_ms } = msg {
+                    awaiting_pong = None;
+                    lock(clock).pong_received(server_time_ms, now_ms());
+                }
+                if events.send(Ok(msg)).await.is_err() {
+                    return Err(ClientError::Closed);
+                }
+            }
+            command = commands.recv() => match command {
+                Some(Command::Send(msg)) => send(socket, &msg, encoding).await?,
+                Some(Command::Close) | None => return Err(ClientError::Closed),
+            },
+            now = keepalive.tick() => {
+                if awaiting_pong.is_some_and(|sent| now - sent >= config.keepalive_timeout) {
+                    return Err(ClientError::KeepaliveTimeout);
+                }
+                awaiting_pong.get_or_insert(now);
+                let client_time_ms = now_ms();
+                let ping = ClientMessage::Ping {
+                    client_time_ms,
+                    request_id: None,
+                };
+                send(socket, &ping, encoding).await?;
+                lock(clock).ping_sent(client_time_ms);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::fixtures;
+
+    pub(super) fn configure() -> ClientMessage {
+        fixtures::client_messages()
+            .into_iter()
+            .find(|msg| matches!(msg, ClientMessage::Configure { .. }))
+            .expect("configure fixture")
+    }
+
+    pub(super) fn hello_ok() -> ServerMessage {
+        let mut msg = fixtures::server_messages()
+            .into_iter()
+            .find(|msg| matches!(msg, ServerMessage::HelloOk { .. }))
+            .expect("hello_ok fixture");
+        if let ServerMessage::HelloOk { encoding, .. } = &mut msg {
+            *encoding = None;
+        }
+        msg
+    }
+
+    pub(super) async fn recv(socket: &mut WebSocketStream<TcpStream>) -> Option<ClientMessage> {
+        loop {
+            match socket.next().await?.ok()? {
+                Message::Text(text) => {
+                    return Some(ClientMessage::from_text(text.as_str()).expect("client frame"))
+                }
+                Message::Close(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
+    pub(super) async fn reply(socket: &mut WebSocketStream<TcpStream>, msg: &ServerMessage) {
+        socket
+            .send(Message::text(msg.to_text().expect("serialize")))
+            .await
+            .expect("send");
+    }
+
+    /// Serves one connection on a loopback port with `script`.
+    async fn loopback<F, Fut>(script: F) -> String
+    where
+        F: FnOnce(WebSocketStream<TcpStream>) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = ()> + Send,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("ws://{}", listener.local_addr().expect("addr"));
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let socket = tokio_tungstenite::accept_async(stream)
+                .await
+                .expect("upgrade");
+            script(socket).await;
+        });
+        url
+    }
+
+    pub(super) fn config(url: String) -> ClientConfig {
+        let mut config = ClientConfig::new(url, configure());
+        config.keepalive_interval = Duration::from_millis(20);
+        config.keepalive_timeout = Duration::from_millis(100);
+        config
+    }
+
+    #[tokio::test]
+    async fn configures_then_streams_messages_and_sends_commands() {
+        let url = loopback(|mut socket| async move {
+            assert_eq!(recv(&mut socket).await, Some(configure()));
+            reply(&mut socket, &hello_ok()).await;
+            loop {
+                match recv(&mut socket).await {
+                    Some(ClientMessage::Ping { .. }) => {
+                        reply(&mut socket, &ServerMessage::Pong { server_time_ms: 1 }).await;
+                    }
+                    Some(msg) => {
+                        let request_id = msg.request_id().expect("request id");
+                        reply(&mut socket, &ServerMessage::Ack { request_id }).await;
+                    }
+                    None => return,
+                }
+            }
+        })
+        .await;
+
+        let mut client = StreamClient::connect(config(url)).await.expect("connect");
+        assert_eq!(client.session_id(), 9);
+        assert_eq!(client.next().await, Some(hello_ok()));
+
+        let unwatch = ClientMessage::UpdateWatchWallets {
+            watch_wallets: Vec::new(),
+            request_id: Some(7),
+        };
+        client.sender().send(unwatch).expect("send");
+        let (mut saw_pong, mut saw_ack) = (false, false);
+        while !(saw_pong && saw_ack) {
+            match client.next().await.expect("message") {
+                ServerMessage::Pong { .. } => saw_pong = true,
+                ServerMessage::Ack { request_id: 7 } => saw_ack = true,
+                other => panic!("unexpected {other:?}"),
+            }
+        }
+        assert!(client.clock().rtt_ms().is_some());
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn rejects_handshake_errors() {
+        for code in [ErrorCode::AuthFailed, ErrorCode::InvalidStrategy] {
+            let error = ServerMessage::Error {
+                code: code.clone(),
+                message: "rejected".into(),
+                request_id: None,
+            };
+            let url = loopback(|mut socket| async move {
+                recv(&mut socket).await;
+                reply(&mut socket, &error).await;
+                let _ = recv(&mut socket).await;
+            })
+            .await;
+
+            match StreamClient::connect(config(url)).await {
+                Err(ClientError::Rejected { code: rejected, .. }) => assert_eq!(rejected, code),
+                other => panic!("expected rejection, got {other:?}"),
+            }
+        }
+    }
+
+    fn hello_ok_with(chosen: Encoding) -> ServerMessage {
+        let mut msg = hello_ok();
+        if let ServerMessage::HelloOk { encoding, .. } = &mut msg {
+            *encoding = Some(chosen);
+        }
+        msg
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn switches_to_the_negotiated_encoding() {
+        let url = loopback(|mut socket| async move {
+            recv(&mut socket).await;
+            reply(&mut socket, &hello_ok_with(Encoding::MessagePack)).await;
+            while let Some(Ok(frame)) = socket.next().await {
+                let Message::Binary(bytes) = frame else {
+                    continue;
+                };
+                let msg = ClientMessage::decode_binary(&bytes).expect("msgpack command");
+                if let Some(request_id) = msg.request_id() {
+                    let ack = ServerMessage::Ack { request_id };
+                    let frame = Message::binary(ack.encode_binary().expect("encode"));
+                    socket.send(frame).await.expect("send");
+                }
+            }
+        })
+        .await;
+
+        let mut client = StreamClient::connect(config(url)).await.expect("connect");
+        assert_eq!(
+            client.next().await,
+            Some(hello_ok_with(Encoding::MessagePack))
+        );
+        client
+            .send(ClientMessage::UpdateWatchWallets {
+                watch_wallets: Vec::new(),
+                request_id: Some(3),
+            })
+            .expect("send");
+        let ack = time::timeout(Duration::from_secs(5), async {
+            loop {
+                match client.next().await {
+                    Some(ServerMessage::Ack { request_id }) => return Some(request_id),
+                    Some(_) => {}
+                    None => return None,
+                }
+            }
+        })
+        .await
+        .expect("ack in time");
+        assert_eq!(ack, Some(3));
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn rejects_unsupported_encodings() {
+        let url = loopback(|mut socket| async move {
+            recv(&mut socket).await;
+            reply(&mut socket, &hello_ok_with(Encoding::from("protobuf"))).await;
+            let _ = recv(&mut socket).await;
+        })
+        .await;
+
+        match StreamClient::connect(config(url)).await {
+            Err(ClientError::UnsupportedEncoding(encoding)) => {
+                assert_eq!(encoding.as_str(), "protobuf")
+            }
+            other => panic!("expected unsupported encoding, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn drops_connection_when_pings_go_unanswered() {
+        let url = loopback(|mut socket| async move {
+            recv(&mut socket).await;
+            reply(&mut socket, &hello_ok()).await;
+            while recv(&mut socket).await.is_some() {}
+        })
+        .await;
+
+        let mut client = StreamClient::connect(config(url)).await.expect("connect");
+        assert_eq!(client.next().await, Some(hello_ok()));
+        assert_eq!(client.next().await, None);
+        assert!(matches!(
+            client.error(),
+            Some(ClientError::KeepaliveTimeout)
+        ));
+    }
+}
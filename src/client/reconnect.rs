@@ -0,0 +1,950 @@
+//! Automatic reconnection with configuration replay.
+//!
+//! [`ReconnectingClient`] wraps [`StreamClient`] and reconnects with
+//! exponential backoff when the connection drops. [`ReplayState`] records the
+//! configuration commands that took effect, so each new session receives the
+//! latest `Configure` (with wallet, watch-wallet and strategy updates folded
+//! in) followed by the per-position strategy overrides. Updates sent with a
+//! `request_id` take effect on their `Ack`; unanswered ones are sent again
+//! after reconnecting.
+//!
+//! Reconnecting stops after an error the server will not accept on retry,
+//! such as `auth_failed`.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time;
+
+use super::{
+    lock, ClientConfig, ClientError, Command, CommandSender, SharedClock, StreamClient,
+    EVENT_BUFFER,
+};
+use crate::{ClientMessage, ClockSync, ServerMessage, StrategyConfigMsg};
+
+/// Backoff and retry limits for [`ReconnectingClient`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnect attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound for the doubling delay between attempts.
+    pub max_backoff: Duration,
+    /// Consecutive failed attempts before giving up; `None` retries forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before reconnect attempt `attempt`, counting from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// The configuration a new session needs to continue where the last one
+/// stopped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayState {
+    configure: ClientMessage,
+    overrides: BTreeMap<u64, StrategyConfigMsg>,
+    /// Updates not folded in yet, in send order.
+    log: VecDeque<Logged>,
+}
+
+/// An update waiting in [`ReplayState`] until it and every earlier update
+/// are settled.
+#[derive(Debug, Clone, PartialEq)]
+struct Logged {
+    msg: ClientMessage,
+    outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl ReplayState {
+    /// Starts from the initial `Configure` command.
+    pub fn new(configure: ClientMessage) -> Self {
+        Self {
+            configure: without_request_id(configure),
+            overrides: BTreeMap::new(),
+            log: VecDeque::new(),
+        }
+    }
+
+    /// `Configure` with every recorded update folded in.
+    pub fn configure(&self) -> &ClientMessage {
+        &self.configure
+    }
+
+    /// Per-position strategy overrides, by position ID.
+    pub fn overrides(&self) -> &BTreeMap<u64, StrategyConfigMsg> {
+        &self.overrides
+    }
+
+    /// Configuration updates not folded in yet, in send order: those waiting
+    /// for an `Ack` and any sent after them. Rejected updates are left out.
+    pub fn pending(&self) -> impl Iterator<Item = &ClientMessage> {
+        self.log
+            .iter()
+            .filter(|logged| logged.outcome != Outcome::Rejected)
+            .map(|logged| &logged.msg)
+    }
+
+    /// Records a command sent to the server.
+    ///
+    /// Configuration updates are folded into the replayed state in the order
+    /// they were sent: an update with a `request_id` once the server
+    /// acknowledges it, and any update only once every earlier one is
+    /// settled. Other commands are ignored.
+    pub fn record(&mut self, msg: &ClientMessage) {
+        let is_update = matches!(
+            msg,
+            ClientMessage::Configure { .. }
+                | ClientMessage::UpdateStrategy { .. }
+                | ClientMessage::UpdateWallets { .. }
+                | ClientMessage::UpdateWatchWallets { .. }
+                | ClientMessage::UpdatePositionStrategy { .. }
+        );
+        if !is_update {
+            return;
+        }
+        let outcome = match msg.request_id() {
+            Some(_) => Outcome::Pending,
+            None => Outcome::Accepted,
+        };
+        if outcome == Outcome::Accepted && self.log.is_empty() {
+            self.apply(msg);
+        } else {
+            self.log.push_back(Logged {
+                msg: msg.clone(),
+                outcome,
+            });
+        }
+    }
+
+    fn apply(&mut self, msg: &ClientMessage) {
+        match (msg, &mut self.configure) {
+            (ClientMessage::Configure { .. }, _) => {
+                self.configure = without_request_id(msg.clone());
+                self.overrides.clear();
+            }
+            (
+                ClientMessage::UpdateStrategy { strategy, .. },
+                ClientMessage::Configure {
+                    strategy: current, ..
+                },
+            ) => *current = strategy.clone(),
+            (
+                ClientMessage::UpdateWallets { wallet_pubkeys, .. },
+                ClientMessage::Configure {
+                    wallet_pubkeys: current,
+                    ..
+                },
+            ) => current.clone_from(wallet_pubkeys),
+            (
+                ClientMessage::UpdateWatchWallets { watch_wallets, .. },
+                ClientMessage::Configure {
+                    watch_wallets: current,
+                    ..
+                },
+            ) => current.clone_from(watch_wallets),
+            (
+                ClientMessage::UpdatePositionStrategy {
+                    position_id,
+                    strategy,
+                    ..
+                },
+                _,
+            ) => {
+                self.overrides.insert(*position_id, strategy.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// Settles pending updates on `Ack` and `Error`, and drops the override
+    /// of a position the server reports as closed.
+    pub fn observe(&mut self, msg: &ServerMessage) {
+        match msg {
+            ServerMessage::Ack { request_id } => self.settle(*request_id, Outcome::Accepted),
+            ServerMessage::Error {
+                request_id: Some(request_id),
+                ..
+            } => self.settle(*request_id, Outcome::Rejected),
+            ServerMessage::PositionClosed { position_id, .. } => {
+                self.overrides.remove(position_id);
+            }
+            ServerMessage::Batch { messages } => messages.iter().for_each(|msg| self.observe(msg)),
+            _ => {}
+        }
+    }
+
+    /// Settles the update sent with `request_id`, then folds in the settled
+    /// updates at the front of the log.
+    fn settle(&mut self, request_id: u64, outcome: Outcome) {
+        let Some(logged) = self.log.iter_mut().find(|logged| {
+            logged.outcome == Outcome::Pending && logged.msg.request_id() == Some(request_id)
+        }) else {
+            return;
+        };
+        logged.outcome = outcome;
+        while let Some(logged) = self.log.front() {
+            match logged.outcome {
+                Outcome::Pending => break,
+                Outcome::Accepted => {
+                    let logged = self.log.pop_front().expect("front exists");
+                    self.apply(&logged.msg);
+                }
+                Outcome::Rejected => {
+                    self.log.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Overrides to re-send once a new session is up.
+    pub fn override_commands(&self) -> impl Iterator<Item = ClientMessage> + '_ {
+        self.overrides.iter().map(|(&position_id, strategy)| {
+            ClientMessage::UpdatePositionStrategy {
+                position_id,
+                strategy: strategy.clone(),
+                request_id: None,
+            }
+        })
+    }
+}
+
+fn without_request_id(mut msg: ClientMessage) -> ClientMessage {
+    if let ClientMessage::Configure { request_id, .. } = &mut msg {
+        *request_id = None;
+    }
+    msg
+}
+
+/// Item of a [`ReconnectingClient`] stream.
+// Messages are almost every item, so boxing them would cost an allocation
+// each for no gain.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum StreamEvent {
+    /// A message from the server.
+    Message(ServerMessage),
+    /// The connection dropped; a reconnect follows unless the stream ends.
+    Disconnected {
+        /// Why the connection ended; `None` after a clean close.
+        error: Option<ClientError>,
+    },
+    /// A new session was configured and the overrides re-sent. Its `HelloOk`
+    /// follows as a message.
+    Reconnected {
+        /// Session identifier of the new session.
+        session_id: u64,
+        /// Attempts it took to reconnect.
+        attempts: u32,
+    },
+}
+
+/// A [`StreamClient`] that reconnects and replays its configuration.
+///
+/// Commands queued while disconnected are sent once the next session is
+/// configured.
+#[derive(Debug)]
+pub struct ReconnectingClient {
+    events: mpsc::Receiver<Result<StreamEvent, ClientError>>,
+    sender: CommandSender,
+    task: JoinHandle<()>,
+    error: Option<ClientError>,
+    clock: SharedClock,
+}
+
+impl ReconnectingClient {
+    /// Connects once, failing if that does, and reconnects from then on.
+    pub async fn connect(
+        config: ClientConfig,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, ClientError> {
+        let clock = SharedClock::default();
+        let client = StreamClient::connect_with_clock(config.clone(), clock.clone()).await?;
+        let replay = ReplayState::new(config.configure.clone());
+        let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+        let (events_tx, events_rx) = mpsc::channel(EVENT_BUFFER);
+        let supervisor = Supervisor {
+            config,
+            policy,
+            replay,
+            commands: commands_rx,
+            events: events_tx,
+            clock: clock.clone(),
+            unsent: Vec::new(),
+        };
+        let task = tokio::spawn(supervisor.run(client));
+        Ok(Self {
+            events: events_rx,
+            sender: CommandSender {
+                commands: commands_tx,
+            },
+            task,
+            error: None,
+            clock,
+        })
+    }
+
+    /// Queues a command for sending.
+    pub fn send(&self, msg: ClientMessage) -> Result<(), ClientError> {
+        self.sender.send(msg)
+    }
+
+    /// Returns a cloneable handle for sending commands from other tasks.
+    pub fn sender(&self) -> CommandSender {
+        self.sender.clone()
+    }
+
+    /// Why reconnecting stopped, once the stream has returned `None`.
+    pub fn error(&self) -> Option<&ClientError> {
+        self.error.as_ref()
+    }
+
+    /// Latency and clock offset estimated across all connections so far.
+    pub fn clock(&self) -> ClockSync {
+        lock(&self.clock).clone()
+    }
+
+    /// Closes the connection and stops reconnecting.
+    pub async fn close(mut self) {
+        let _ = self.sender.commands.send(Command::Close);
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Stream for ReconnectingClient {
+    type Item = StreamEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<StreamEvent>> {
+        match self.events.poll_recv(cx) {
+            Poll::Ready(Some(Ok(event))) => Poll::Ready(Some(event)),
+            Poll::Ready(Some(Err(err))) => {
+                self.error = Some(err);
+                Poll::Ready(None)
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Drop for ReconnectingClient {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Why a session ended.
+enum Ended {
+    /// The consumer closed the client or dropped it.
+    Closed,
+    /// The connection dropped.
+    Dropped(Option<ClientError>),
+}
+
+struct Supervisor {
+    config: ClientConfig,
+    policy: ReconnectPolicy,
+    replay: ReplayState,
+    commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::Sender<Result<StreamEvent, ClientError>>,
+    clock: SharedClock,
+    /// Commands the dropped connection could not take, in order.
+    unsent: Vec<ClientMessage>,
+}
+
+impl Supervisor {
+    async fn run(mut self, mut client: StreamClient) {
+        loop {
+            let error = match self.session(&mut client).await {
+                Ended::Closed => return client.close().await,
+                Ended::Dropped(error) => error,
+            };
+            let fatal = match &error {
+                Some(ClientError::Rejected { code, message }) if !code.is_retryable() => {
+                    Some(ClientError::Rejected {
+                        code: code.clone(),
+                        message: message.clone(),
+                    })
+                }
+                _ => None,
+            };
+            if !self.emit(Ok(StreamEvent::Disconnected { error })).await {
+                return;
+            }
+            if let Some(err) = fatal {
+                let _ = self.events.send(Err(err)).await;
+                return;
+            }
+            client = match self.reconnect().await {
+                Ok(Some(client)) => client,
+                Ok(None) => return,
+                Err(err) => {
+                    let _ = self.events.send(Err(err)).await;
+                    return;
+                }
+            };
+        }
+    }
+
+    async fn emit(&self, event: Result<StreamEvent, ClientError>) -> bool {
+        self.events.send(event).await.is_ok()
+    }
+
+    /// Forwards messages and commands until the connection ends.
+    async fn session(&mut self, client: &mut StreamClient) -> Ended {
+        // A fatal error reported by the server before it closes the socket.
+        let mut fatal = None;
+        loop {
+            tokio::select! {
+                msg = client.next() => {
+                    let Some(msg) = msg else {
+                        return Ended::Dropped(fatal.or_else(|| client.error.take()));
+                    };
+                    self.replay.observe(&msg);
+                    if let ServerMessage::Error { code, message, .. } = &msg {
+                        if code.is_fatal_for_session() {
+                            fatal = Some(ClientError::Rejected {
+                                code: code.clone(),
+                                message: message.clone(),
+                            });
+                        }
+                    }
+                    if !self.emit(Ok(StreamEvent::Message(msg))).await {
+                        return Ended::Closed;
+                    }
+                }
+                command = self.commands.recv() => match command {
+                    Some(Command::Send(msg)) => {
+                        self.replay.record(&msg);
+                        // A failed send means the connection is going down;
+                        // the next `client.next()` reports why.
+                        self.send(client, *msg);
+                    }
+                    Some(Command::Close) | None => return Ended::Closed,
+                },
+            }
+        }
+    }
+
+    /// Sends a command, keeping it for the next session if the connection is
+    /// already gone.
+    fn send(&mut self, client: &StreamClient, msg: ClientMessage) {
+        if client.send(msg.clone()).is_err() {
+            self.unsent.push(msg);
+        }
+    }
+
+    /// Reconnects with backoff. `Ok(None)` means the consumer closed the
+    /// client while waiting.
+    async fn reconnect(&mut self) -> Result<Option<StreamClient>, ClientError> {
+        // Updates not folded into the replayed `Configure` go again in the
+        // order they were sent, then whatever else the old session could not
+        // take.
+        let mut queued: Vec<ClientMessage> = self.replay.pending().cloned().collect();
+        for msg in self.unsent.drain(..) {
+            if !queued.contains(&msg) {
+                queued.push(msg);
+            }
+        }
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let sleep = time::sleep(self.policy.backoff(attempts));
+            tokio::pin!(sleep);
+            loop {
+                tokio::select! {
+                    () = &mut sleep => break,
+                    command = self.commands.recv() => match command {
+                        Some(Command::Send(msg)) => {
+                            self.replay.record(&msg);
+                            queued.push(*msg);
+                        }
+                        Some(Command::Close) | None => return Ok(None),
+                    },
+                }
+            }
+
+            let mut config = self.config.clone();
+            config.configure = self.replay.configure().clone();
+            // Pings of the dropped connection will never be answered.
+            lock(&self.clock).clear_pending();
+            let err = match StreamClient::connect_with_clock(config, self.clock.clone()).await {
+                Ok(client) => {
+                    let overrides: Vec<ClientMessage> = self.replay.override_commands().collect();
+                    for msg in overrides.into_iter().chain(queued) {
+                        self.send(&client, msg);
+                    }
+                    let session_id = client.session_id();
+                    let event = StreamEvent::Reconnected {
+                        session_id,
+                        attempts,
+                    };
+                    return Ok(self.emit(Ok(event)).await.then_some(client));
+                }
+                Err(err) => err,
+            };
+            let retryable = match &err {
+                ClientError::Rejected { code, .. } => code.is_retryable(),
+                ClientError::UnsupportedEncoding(_) => false,
+                _ => true,
+            };
+            if !retryable || self.policy.max_attempts.is_some_and(|max| attempts >= max) {
+                return Err(err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::SinkExt;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::WebSocketStream;
+
+    use super::super::tests::{config, configure, hello_ok, recv, reply};
+    use super::*;
+    use crate::{ErrorCode, Pubkey58};
+
+    fn strategy(stop_loss_pct: f64) -> StrategyConfigMsg {
+        StrategyConfigMsg {
+            stop_loss_pct,
+            ..StrategyConfigMsg::default()
+        }
+    }
+
+    fn policy() -> ReconnectPolicy {
+        ReconnectPolicy {
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+            max_attempts: Some(5),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let policy = policy();
+        let delays: Vec<u64> = (1..=5)
+            .map(|n| policy.backoff(n).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [10, 20, 40, 40, 40]);
+        assert_eq!(policy.backoff(200), policy.max_backoff);
+    }
+
+    #[test]
+    fn replay_state_folds_updates_into_configure() {
+        let mut replay = ReplayState::new(configure().with_request_id(1));
+        let wallets = vec![Pubkey58::from_bytes(&[9; 32])];
+        replay.record(&ClientMessage::UpdateWallets {
+            wallet_pubkeys: wallets.clone(),
+            request_id: Some(2),
+        });
+        replay.record(&ClientMessage::UpdateStrategy {
+            strategy: strategy(7.0),
+            request_id: None,
+        });
+        replay.record(&ClientMessage::UpdatePositionStrategy {
+            position_id: 4,
+            strategy: strategy(3.0),
+            request_id: None,
+        });
+        replay.record(&ClientMessage::Ping {
+            client_time_ms: 1,
+            request_id: None,
+        });
+        // The later updates wait behind the unanswered one.
+        assert_eq!(replay.pending().count(), 3);
+        assert_eq!(replay.override_commands().count(), 0);
+        replay.observe(&ServerMessage::Ack { request_id: 2 });
+        assert_eq!(replay.pending().count(), 0);
+
+        let ClientMessage::Configure {
+            wallet_pubkeys,
+            strategy: current,
+            request_id,
+            ..
+        } = replay.configure()
+        else {
+            panic!("expected configure");
+        };
+        assert_eq!(wallet_pubkeys, &wallets);
+        assert_eq!(current, &strategy(7.0));
+        assert_eq!(*request_id, None);
+        assert_eq!(replay.override_commands().count(), 1);
+
+        let closed = crate::fixtures::server_messages()
+            .into_iter()
+            .find_map(|msg| match msg {
+                ServerMessage::PositionClosed { .. } => Some(msg),
+                _ => None,
+            })
+            .expect("position_closed fixture");
+        let ServerMessage::PositionClosed { position_id, .. } = closed else {
+            unreachable!();
+        };
+        replay.record(&ClientMessage::UpdatePositionStrategy {
+            position_id,
+            strategy: strategy(3.0),
+            request_id: None,
+        });
+        replay.observe(&ServerMessage::Batch {
+            messages: vec![closed],
+        });
+        assert_eq!(replay.overrides().keys().collect::<Vec<_>>(), [&4]);
+    }
+
+    #[test]
+    fn replay_state_discards_rejected_updates() {
+        let mut replay = ReplayState::new(configure());
+        let before = replay.configure().clone();
+        replay.record(&ClientMessage::UpdateStrategy {
+            strategy: strategy(-1.0),
+            request_id: Some(3),
+        });
+        replay.record(&ClientMessage::UpdatePositionStrategy {
+            position_id: 4,
+            strategy: strategy(2.0),
+            request_id: Some(4),
+        });
+        assert_eq!(replay.configure(), &before);
+        assert_eq!(replay.pending().count(), 2);
+
+        replay.observe(&ServerMessage::Error {
+            code: ErrorCode::InvalidStrategy,
+            message: "stop_loss_pct must not be negative".into(),
+            request_id: Some(3),
+        });
+        replay.observe(&ServerMessage::Error {
+            code: ErrorCode::UnknownPosition,
+            message: "no position 4".into(),
+            request_id: Some(4),
+        });
+        assert_eq!(replay.configure(), &before);
+        assert!(replay.overrides().is_empty());
+        assert_eq!(replay.pending().count(), 0);
+    }
+
+    #[test]
+    fn replay_state_folds_updates_in_send_order() {
+        let update = |stop_loss_pct, request_id| ClientMessage::UpdateStrategy {
+            strategy: strategy(stop_loss_pct),
+            request_id,
+        };
+        let current = |replay: &ReplayState| match replay.configure() {
+            ClientMessage::Configure { strategy, .. } => strategy.clone(),
+            _ => panic!("expected configure"),
+        };
+
+        // An update acknowledged late does not overwrite a later one.
+        let mut replay = ReplayState::new(configure());
+        let before = current(&replay);
+        replay.record(&update(5.0, Some(5)));
+        replay.record(&update(6.0, None));
+        assert_eq!(current(&replay), before);
+        assert_eq!(
+            replay.pending().cloned().collect::<Vec<_>>(),
+            [update(5.0, Some(5)), update(6.0, None)]
+        );
+        replay.observe(&ServerMessage::Ack { request_id: 5 });
+        assert_eq!(current(&replay), strategy(6.0));
+        assert_eq!(replay.pending().count(), 0);
+
+        // Answers arriving out of order still fold in send order.
+        replay.record(&update(7.0, Some(7)));
+        replay.record(&update(8.0, Some(8)));
+        replay.record(&update(9.0, Some(9)));
+        replay.observe(&ServerMessage::Ack { request_id: 8 });
+        replay.observe(&ServerMessage::Error {
+            code: ErrorCode::InvalidStrategy,
+            message: "rejected".into(),
+            request_id: Some(9),
+        });
+        assert_eq!(current(&replay), strategy(6.0));
+        assert_eq!(
+            replay.pending().cloned().collect::<Vec<_>>(),
+            [update(7.0, Some(7)), update(8.0, Some(8))]
+        );
+        replay.observe(&ServerMessage::Ack { request_id: 7 });
+        assert_eq!(current(&replay), strategy(8.0));
+        assert_eq!(replay.pending().count(), 0);
+
+        // A rejected update lets the ones behind it through.
+        replay.record(&update(10.0, Some(10)));
+        replay.record(&update(11.0, None));
+        replay.observe(&ServerMessage::Error {
+            code: ErrorCode::InvalidStrategy,
+            message: "rejected".into(),
+            request_id: Some(10),
+        });
+        assert_eq!(current(&replay), strategy(11.0));
+    }
+
+    fn hello_ok_for(session_id: u64) -> ServerMessage {
+        let mut msg = hello_ok();
+        if let ServerMessage::HelloOk { session_id: id, .. } = &mut msg {
+            *id = session_id;
+        }
+        msg
+    }
+
+    #[tokio::test]
+    async fn replays_configuration_and_overrides_after_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("ws://{}", listener.local_addr().expect("addr"));
+        let server = tokio::spawn(async move {
+            let accept = || async {
+                let (stream, _) = listener.accept().await.expect("accept");
+                tokio_tungstenite::accept_async(stream)
+                    .await
+                    .expect("upgrade")
+            };
+
+            let mut socket = accept().await;
+            assert_eq!(recv(&mut socket).await, Some(configure()));
+            reply(&mut socket, &hello_ok_for(1)).await;
+            let mut updates = 0;
+            while updates < 2 {
+                match recv(&mut socket).await.expect("update") {
+                    ClientMessage::Ping { .. } => {}
+                    _ => updates += 1,
+                }
+            }
+            socket.close(None).await.expect("close");
+
+            let mut socket = accept().await;
+            let Some(ClientMessage::Configure {
+                strategy: replayed, ..
+            }) = recv(&mut socket).await
+            else {
+                panic!("expected configure");
+            };
+            assert_eq!(replayed, strategy(9.0));
+            reply(&mut socket, &hello_ok_for(2)).await;
+            loop {
+                match recv(&mut socket).await.expect("override") {
+                    ClientMessage::UpdatePositionStrategy { position_id, .. } => {
+                        assert_eq!(position_id, 4);
+                        break;
+                    }
+                    ClientMessage::Ping { .. } => {}
+                    other => panic!("unexpected {other:?}"),
+                }
+            }
+            let _ = socket.send(Message::Close(None)).await;
+        });
+
+        let mut client = ReconnectingClient::connect(config(url), policy())
+            .await
+            .expect("connect");
+        assert!(matches!(
+            client.next().await,
+            Some(StreamEvent::Message(ServerMessage::HelloOk {
+                session_id: 1,
+                ..
+            }))
+        ));
+        client
+            .send(ClientMessage::UpdateStrategy {
+                strategy: strategy(9.0),
+                request_id: None,
+            })
+            .expect("send");
+        client
+            .send(ClientMessage::UpdatePositionStrategy {
+                position_id: 4,
+                strategy: strategy(2.0),
+                request_id: None,
+            })
+            .expect("send");
+
+        assert!(matches!(
+            client.next().await,
+            Some(StreamEvent::Disconnected { error: None })
+        ));
+        assert!(matches!(
+            client.next().await,
+            Some(StreamEvent::Reconnected {
+                session_id: 2,
+                attempts: 1
+            })
+        ));
+        assert!(matches!(
+            client.next().await,
+            Some(StreamEvent::Message(ServerMessage::HelloOk {
+                session_id: 2,
+                ..
+            }))
+        ));
+        server.await.expect("server script");
+        client.close().await;
+    }
+
+    /// Next command other than a keepalive `Ping`.
+    async fn next_update(socket: &mut WebSocketStream<TcpStream>) -> ClientMessage {
+        loop {
+            match recv(socket).await.expect("update") {
+                ClientMessage::Ping { .. } => {}
+                msg => return msg,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn resends_unanswered_updates_after_reconnect() {
+        let update = ClientMessage::UpdateStrategy {
+            strategy: strategy(9.0),
+            request_id: Some(5),
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("ws://{}", listener.local_addr().expect("addr"));
+        let expected = update.clone();
+        let server = tokio::spawn(async move {
+            let accept = || async {
+                let (stream, _) = listener.accept().await.expect("accept");
+                tokio_tungstenite::accept_async(stream)
+                    .await
+                    .expect("upgrade")
+            };
+            // Drop the connection without answering the update.
+            let mut socket = accept().await;
+            recv(&mut socket).await;
+            reply(&mut socket, &hello_ok_for(1)).await;
+            assert_eq!(next_update(&mut socket).await, expected);
+            socket.close(None).await.expect("close");
+
+            // The replayed configuration leaves the update out; it is re-sent.
+            let mut socket = accept().await;
+            let replayed = recv(&mut socket).await.expect("configure");
+            assert_eq!(replayed.strategy(), configure().strategy());
+            reply(&mut socket, &hello_ok_for(2)).await;
+            assert_eq!(next_update(&mut socket).await, expected);
+            reply(&mut socket, &ServerMessage::Ack { request_id: 5 }).await;
+            while recv(&mut socket).await.is_some() {}
+        });
+
+        let mut client = ReconnectingClient::connect(config(url), policy())
+            .await
+            .expect("connect");
+        client.send(update).expect("send");
+        loop {
+            match client.next().await.expect("event") {
+                StreamEvent::Message(ServerMessage::Ack { request_id }) => {
+                    assert_eq!(request_id, 5);
+                    break;
+                }
+                StreamEvent::Disconnected { error } => assert!(error.is_none()),
+                _ => {}
+            }
+        }
+        client.close().await;
+        server.await.expect("server script");
+    }
+
+    #[tokio::test]
+    async fn keeps_commands_the_dropped_connection_could_not_take() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("ws://{}", listener.local_addr().expect("addr"));
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let mut socket = tokio_tungstenite::accept_async(stream)
+                .await
+                .expect("upgrade");
+            recv(&mut socket).await;
+            reply(&mut socket, &hello_ok_for(1)).await;
+            let _ = socket.close(None).await;
+        });
+
+        let mut client = StreamClient::connect(config(url)).await.expect("connect");
+        while client.next().await.is_some() {}
+
+        let (_commands_tx, commands) = mpsc::unbounded_channel();
+        let (events, _events_rx) = mpsc::channel(1);
+        let mut supervisor = Supervisor {
+            config: config("ws://127.0.0.1:1".to_string()),
+            policy: policy(),
+            replay: ReplayState::new(configure()),
+            commands,
+            events,
+            clock: SharedClock::default(),
+            unsent: Vec::new(),
+        };
+        let close = ClientMessage::ClosePosition {
+            position_id: Some(4),
+            token_account: None,
+            request_id: None,
+        };
+        supervisor.send(&client, close.clone());
+        assert_eq!(supervisor.unsent, [close]);
+    }
+
+    #[tokio::test]
+    async fn stops_after_non_retryable_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let url = format!("ws://{}", listener.local_addr().expect("addr"));
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let mut socket = tokio_tungstenite::accept_async(stream)
+                .await
+                .expect("upgrade");
+            recv(&mut socket).await;
+            reply(&mut socket, &hello_ok_for(1)).await;
+            let error = ServerMessage::Error {
+                code: ErrorCode::AuthFailed,
+                message: "key revoked".into(),
+                request_id: None,
+            };
+            reply(&mut socket, &error).await;
+            let _ = socket.close(None).await;
+        });
+
+        let mut client = ReconnectingClient::connect(config(url), policy())
+            .await
+            .expect("connect");
+        assert!(matches!(client.next().await, Some(StreamEvent::Message(_))));
+        assert!(matches!(
+            client.next().await,
+            Some(StreamEvent::Message(ServerMessage::Error { .. }))
+        ));
+        assert!(matches!(
+            client.next().await,
+            Some(StreamEvent::Disconnected { error: Some(_) })
+        ));
+        assert!(client.next().await.is_none());
+        assert!(matches!(
+            client.error(),
+            Some(ClientError::Rejected {
+                code: ErrorCode::AuthFailed,
+                ..
+            })
+        ));
+    }
+}
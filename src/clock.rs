@@ -0,0 +1,330 @@
+//! Clock offset and latency estimation from keepalive exchanges.
+//!
+//! Each `Ping` carries the client's send time and each `Pong` the server's
+//! time. Servers answer pings in order, so [`ClockSync`] pairs them first in,
+//! first out and derives, NTP-style, the round-trip time and the server clock
+//! offset, assuming the server stamped the `Pong` halfway through the round
+//! trip:
+//!
+//! ```text
+//! rtt    = received - sent
+//! offset = server_time - (sent + received) / 2
+//! ```
+//!
+//! The error of an offset sample is at most half its round trip, so the
+//! estimate uses the sample with the smallest round trip among the recent
+//! ones. Samples whose round trip is far above the recent median are rejected
+//! as outliers, unless they keep coming, which means the path changed.
+//!
+//! All times are Unix milliseconds; "local" means this machine's clock.
+
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::ServerMessage;
+
+/// Samples kept for the estimate.
+const DEFAULT_WINDOW: usize = 8;
+
+/// Samples needed before outliers are rejected.
+const MIN_SAMPLES_FOR_REJECTION: usize = 3;
+
+/// A round trip is an outlier above `median * FACTOR + SLACK_MS`.
+const OUTLIER_FACTOR: u64 = 3;
+const OUTLIER_SLACK_MS: u64 = 5;
+
+/// Unanswered pings remembered; older ones are assumed lost.
+const MAX_PENDING: usize = 16;
+
+/// Current local time in Unix milliseconds.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// One `Ping`/`Pong` exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSample {
+    /// Local time the `Ping` was sent.
+    pub sent_ms: u64,
+    /// Local time the `Pong` arrived.
+    pub received_ms: u64,
+    /// Round-trip time.
+    pub rtt_ms: u64,
+    /// Server clock minus local clock.
+    pub offset_ms: i64,
+}
+
+impl ClockSample {
+    /// Computes a sample from one exchange, or `None` if the local clock went
+    /// backwards in between.
+    pub fn new(sent_ms: u64, server_time_ms: u64, received_ms: u64) -> Option<Self> {
+        let rtt_ms = received_ms.checked_sub(sent_ms)?;
+        let midpoint = sent_ms + rtt_ms / 2;
+        Some(Self {
+            sent_ms,
+            received_ms,
+            rtt_ms,
+            offset_ms: server_time_ms as i64 - midpoint as i64,
+        })
+    }
+}
+
+/// Estimates round-trip time and server clock offset from keepalives.
+///
+/// Feed it with [`ClockSync::ping_sent`] and [`ClockSync::pong_received`];
+/// [`StreamClient`](crate::client::StreamClient) does this for its own
+/// keepalive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockSync {
+    window: usize,
+    samples: VecDeque<ClockSample>,
+    pending: VecDeque<u64>,
+    /// Consecutive samples rejected as outliers.
+    rejected: usize,
+}
+
+impl Default for ClockSync {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClockSync {
+    /// Creates an estimator keeping the last 8 samples.
+    pub fn new() -> Self {
+        Self::with_window(DEFAULT_WINDOW)
+    }
+
+    /// Creates an estimator keeping the last `window` samples (at least 1).
+    pub fn with_window(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            samples: VecDeque::new(),
+            pending: VecDeque::new(),
+            rejected: 0,
+        }
+    }
+
+    /// Records a `Ping` sent with `client_time_ms`.
+    pub fn ping_sent(&mut self, client_time_ms: u64) {
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(client_time_ms);
+    }
+
+    /// Pairs a `Pong` with the oldest unanswered `Ping`.
+    ///
+    /// Returns the sample if it was accepted into the estimate.
+    pub fn pong_received(&mut self, server_time_ms: u64, local_ms: u64) -> Option<ClockSample> {
+        let sent_ms = self.pending.pop_front()?;
+        self.add_sample(ClockSample::new(sent_ms, server_time_ms, local_ms)?)
+    }
+
+    /// Forgets unanswered pings, e.g. after reconnecting.
+    pub fn clear_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Adds a sample unless it is an outlier; returns it if accepted.
+    pub fn add_sample(&mut self, sample: ClockSample) -> Option<ClockSample> {
+        if self.samples.len() >= MIN_SAMPLES_FOR_REJECTION {
+            let mut rtts: Vec<u64> = self.samples.iter().map(|s| s.rtt_ms).collect();
+            rtts.sort_unstable();
+            let median = rtts[rtts.len() / 2];
+            if sample.rtt_ms > median * OUTLIER_FACTOR + OUTLIER_SLACK_MS {
+                self.rejected += 1;
+                if self.rejected < self.window {
+                    return None;
+                }
+                // A whole window of "outliers" is the new normal.
+                self.samples.clear();
+            }
+        }
+        self.rejected = 0;
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        Some(sample)
+    }
+
+    /// Accepted samples, oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = &ClockSample> {
+        self.samples.iter()
+    }
+
+    fn best(&self) -> Option<&ClockSample> {
+        self.samples.iter().min_by_key(|sample| sample.rtt_ms)
+    }
+
+    /// Round-trip time of the latest accepted sample.
+    pub fn rtt_ms(&self) -> Option<u64> {
+        self.samples.back().map(|sample| sample.rtt_ms)
+    }
+
+    /// Smallest round-trip time among the recent samples.
+    pub fn min_rtt_ms(&self) -> Option<u64> {
+        self.best().map(|sample| sample.rtt_ms)
+    }
+
+    /// Estimated one-way latency: half the latest round trip.
+    pub fn one_way_latency_ms(&self) -> Option<u64> {
+        self.rtt_ms().map(|rtt| rtt / 2)
+    }
+
+    /// Estimated server clock minus local clock.
+    pub fn offset_ms(&self) -> Option<i64> {
+        self.best().map(|sample| sample.offset_ms)
+    }
+
+    /// Translates a server timestamp into local time.
+    ///
+    /// Returns the timestamp unchanged until the first sample.
+    pub fn to_local_ms(&self, server_ms: u64) -> u64 {
+        shift(server_ms, -self.offset_ms().unwrap_or(0))
+    }
+
+    /// Translates a local timestamp into server time.
+    pub fn to_server_ms(&self, local_ms: u64) -> u64 {
+        shift(local_ms, self.offset_ms().unwrap_or(0))
+    }
+
+    /// How long before `local_ms` the server timestamp `server_ms` was, in
+    /// local time; 0 if it lies in the future.
+    pub fn age_at_ms(&self, server_ms: u64, local_ms: u64) -> u64 {
+        local_ms.saturating_sub(self.to_local_ms(server_ms))
+    }
+
+    /// How long ago the server timestamp `server_ms` was.
+    pub fn age_ms(&self, server_ms: u64) -> u64 {
+        self.age_at_ms(server_ms, now_ms())
+    }
+
+    /// Age of a message by its event time, e.g. how stale an
+    /// `ExitSignalWithTx` is before signing it.
+    pub fn staleness_ms(&self, msg: &ServerMessage) -> Option<u64> {
+        msg.event_time_ms().map(|server_ms| self.age_ms(server_ms))
+    }
+}
+
+fn shift(ms: u64, by: i64) -> u64 {
+    ms.saturating_add_signed(by)
+}
+
+impl ServerMessage {
+    /// Server-side time of the event this message reports, in Unix
+    /// milliseconds.
+    ///
+    /// This is `server_time_ms`, `time_ms` for trade ticks,
+    /// `triggered_at_ms` for exit signals and `opened_at_ms` for opened
+    /// positions. Messages without a timestamp return `None`.
+    pub fn event_time_ms(&self) -> Option<u64> {
+        match self {
+            ServerMessage::HelloOk { server_time_ms, .. }
+            | ServerMessage::Pong { server_time_ms }
+            | ServerMessage::PnlUpdate { server_time_ms, .. }
+            | ServerMessage::PnlDelta { server_time_ms, .. }
+            | ServerMessage::LiquiditySnapshot { server_time_ms, .. } => Some(*server_time_ms),
+            ServerMessage::TradeTick { time_ms, .. } => Some(*time_ms),
+            ServerMessage::ExitSignalWithTx {
+                triggered_at_ms, ..
+            } => Some(*triggered_at_ms),
+            ServerMessage::PositionOpened { opened_at_ms, .. } => *opened_at_ms,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exchange with a server clock `offset` ahead and symmetric paths.
+    fn exchange(clock: &mut ClockSync, sent: u64, rtt: u64, offset: i64) -> Option<ClockSample> {
+        clock.ping_sent(sent);
+        let server = shift(sent + rtt / 2, offset);
+        clock.pong_received(server, sent + rtt)
+    }
+
+    #[test]
+    fn estimates_offset_and_rtt() {
+        let mut clock = ClockSync::new();
+        assert_eq!(clock.offset_ms(), None);
+        assert_eq!(clock.to_local_ms(1_000), 1_000);
+
+        let sample = exchange(&mut clock, 10_000, 40, 250).expect("accepted");
+        assert_eq!(sample.rtt_ms, 40);
+        assert_eq!(sample.offset_ms, 250);
+        exchange(&mut clock, 11_000, 20, -100);
+
+        // The shorter round trip wins.
+        assert_eq!(clock.offset_ms(), Some(-100));
+        assert_eq!(clock.rtt_ms(), Some(20));
+        assert_eq!(clock.min_rtt_ms(), Some(20));
+        assert_eq!(clock.one_way_latency_ms(), Some(10));
+        assert_eq!(clock.to_local_ms(11_910), 12_010);
+        assert_eq!(clock.to_server_ms(12_010), 11_910);
+        assert_eq!(clock.age_at_ms(11_910, 12_060), 50);
+        assert_eq!(clock.age_at_ms(20_000, 12_060), 0);
+    }
+
+    #[test]
+    fn pairs_pongs_with_pings_in_order() {
+        let mut clock = ClockSync::new();
+        clock.ping_sent(100);
+        clock.ping_sent(200);
+        assert_eq!(clock.pong_received(130, 160).map(|s| s.rtt_ms), Some(60));
+        assert_eq!(clock.pong_received(230, 250).map(|s| s.rtt_ms), Some(50));
+        assert_eq!(clock.pong_received(300, 300), None);
+
+        clock.ping_sent(400);
+        clock.clear_pending();
+        assert_eq!(clock.pong_received(430, 450), None);
+    }
+
+    #[test]
+    fn rejects_outliers_until_they_persist() {
+        let mut clock = ClockSync::with_window(4);
+        for i in 0..4 {
+            exchange(&mut clock, i * 1_000, 20, 50);
+        }
+        // A congested exchange with a skewed offset is ignored.
+        assert_eq!(exchange(&mut clock, 5_000, 900, 400), None);
+        assert_eq!(clock.offset_ms(), Some(50));
+        assert_eq!(clock.rtt_ms(), Some(20));
+
+        // A lasting change in path is eventually accepted.
+        for i in 0..2 {
+            assert_eq!(exchange(&mut clock, 6_000 + i * 1_000, 300, 80), None);
+        }
+        assert!(exchange(&mut clock, 9_000, 300, 80).is_some());
+        assert_eq!(clock.offset_ms(), Some(80));
+        assert_eq!(clock.samples().count(), 1);
+    }
+
+    #[test]
+    fn event_times_cover_timestamped_messages() {
+        let mut with_time = 0;
+        for msg in crate::fixtures::server_messages() {
+            if let Some(time) = msg.event_time_ms() {
+                assert!(time > 0);
+                with_time += 1;
+            }
+        }
+        assert!(with_time >= 7);
+
+        let clock = ClockSync::new();
+        let signal = crate::fixtures::server_messages()
+            .into_iter()
+            .find(|msg| matches!(msg, ServerMessage::ExitSignalWithTx { .. }))
+            .expect("exit signal fixture");
+        assert!(clock.staleness_ms(&signal).is_some());
+        assert_eq!(
+            clock.staleness_ms(&ServerMessage::Ack { request_id: 1 }),
+            None
+        );
+    }
+}
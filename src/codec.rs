@@ -0,0 +1,209 @@
+//! Binary encodings of the wire messages.
+//!
+//! MessagePack and CBOR frames carry exactly what the JSON text carries:
+//! structs are encoded as maps keyed by field name, so the `type` tag,
+//! optional-field skipping and deserialize aliases (`sell_now`,
+//! `wallet_pubkey`) behave the same in every format.
+
+use crate::{ClientMessage, ServerMessage};
+
+#[cfg(feature = "msgpack")]
+impl ClientMessage {
+    /// Serializes this command as MessagePack.
+    pub fn encode_binary(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// Parses a MessagePack frame into a [`ClientMessage`].
+    pub fn decode_binary(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl ServerMessage {
+    /// Serializes this message as MessagePack.
+    pub fn encode_binary(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// Parses a MessagePack frame into a [`ServerMessage`].
+    pub fn decode_binary(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl ClientMessage {
+    /// Serializes this command as CBOR.
+    pub fn encode_cbor(&self) -> Result<Vec<u8>, ciborium::ser::Error<std::io::Error>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Parses a CBOR frame into a [`ClientMessage`].
+    pub fn decode_cbor(bytes: &[u8]) -> Result<Self, ciborium::de::Error<std::io::Error>> {
+        ciborium::from_reader(bytes)
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl ServerMessage {
+    /// Serializes this message as CBOR.
+    pub fn encode_cbor(&self) -> Result<Vec<u8>, ciborium::ser::Error<std::io::Error>> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Parses a CBOR frame into a [`ServerMessage`].
+    pub fn decode_cbor(bytes: &[u8]) -> Result<Self, ciborium::de::Error<std::io::Error>> {
+        ciborium::from_reader(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    // The transcoders re-encode JSON text without going through the message
+    // types, the way a server with a generic transcoder would.
+
+    #[cfg(feature = "msgpack")]
+    fn json_to_msgpack(json: &str) -> Vec<u8> {
+        let value: serde_json::Value = serde_json::from_str(json).expect("json");
+        rmp_serde::to_vec_named(&value).expect("msgpack")
+    }
+
+    #[cfg(feature = "cbor")]
+    fn json_to_cbor(json: &str) -> Vec<u8> {
+        let value: serde_json::Value = serde_json::from_str(json).expect("json");
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&value, &mut bytes).expect("cbor");
+        bytes
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn every_message_round_trips_msgpack() {
+        for msg in fixtures::client_messages() {
+            let bytes = msg.encode_binary().expect("encode");
+            assert_eq!(ClientMessage::decode_binary(&bytes).expect("decode"), msg);
+        }
+        for msg in fixtures::server_messages() {
+            let bytes = msg.encode_binary().expect("encode");
+            assert_eq!(ServerMessage::decode_binary(&bytes).expect("decode"), msg);
+        }
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_carries_the_json_fields() {
+        fn fields(bytes: &[u8]) -> serde_json::Value {
+            rmp_serde::from_slice(bytes).expect("value")
+        }
+        for msg in fixtures::client_messages() {
+            let json = serde_json::to_value(&msg).expect("json");
+            assert_eq!(fields(&msg.encode_binary().expect("encode")), json);
+            let transcoded = json_to_msgpack(&json.to_string());
+            assert_eq!(
+                ClientMessage::decode_binary(&transcoded).expect("decode"),
+                msg
+            );
+        }
+        for msg in fixtures::server_messages() {
+            let json = serde_json::to_value(&msg).expect("json");
+            assert_eq!(fields(&msg.encode_binary().expect("encode")), json);
+            let transcoded = json_to_msgpack(&json.to_string());
+            assert_eq!(
+                ServerMessage::decode_binary(&transcoded).expect("decode"),
+                msg
+            );
+        }
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn msgpack_accepts_legacy_aliases() {
+        let sell_now = json_to_msgpack(r#"{"type":"sell_now","position_id":123}"#);
+        assert_eq!(
+            ClientMessage::decode_binary(&sell_now).expect("decode"),
+            ClientMessage::RequestExitSignal {
+                position_id: Some(123),
+                token_account: None,
+                slippage_bps: None,
+                request_id: None,
+            }
+        );
+
+        let legacy = r#"{
+            "type":"configure",
+            "wallet_pubkey":"11111111111111111111111111111111",
+            "strategy":{"target_profit_pct":5.0,"stop_loss_pct":1.5}
+        }"#;
+        assert_eq!(
+            ClientMessage::decode_binary(&json_to_msgpack(legacy)).expect("decode"),
+            ClientMessage::from_text(legacy).expect("json")
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn every_message_round_trips_cbor() {
+        for msg in fixtures::client_messages() {
+            let bytes = msg.encode_cbor().expect("encode");
+            assert_eq!(ClientMessage::decode_cbor(&bytes).expect("decode"), msg);
+        }
+        for msg in fixtures::server_messages() {
+            let bytes = msg.encode_cbor().expect("encode");
+            assert_eq!(ServerMessage::decode_cbor(&bytes).expect("decode"), msg);
+        }
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_carries_the_json_fields() {
+        fn fields(bytes: &[u8]) -> serde_json::Value {
+            ciborium::from_reader(bytes).expect("value")
+        }
+        for msg in fixtures::client_messages() {
+            let json = serde_json::to_value(&msg).expect("json");
+            assert_eq!(fields(&msg.encode_cbor().expect("encode")), json);
+            let transcoded = json_to_cbor(&json.to_string());
+            assert_eq!(
+                ClientMessage::decode_cbor(&transcoded).expect("decode"),
+                msg
+            );
+        }
+        for msg in fixtures::server_messages() {
+            let json = serde_json::to_value(&msg).expect("json");
+            assert_eq!(fields(&msg.encode_cbor().expect("encode")), json);
+            let transcoded = json_to_cbor(&json.to_string());
+            assert_eq!(
+                ServerMessage::decode_cbor(&transcoded).expect("decode"),
+                msg
+            );
+        }
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn cbor_accepts_legacy_aliases() {
+        let raw = r#"{"type":"sell_now","token_account":"11111111111111111111111111111111"}"#;
+        assert_eq!(
+            ClientMessage::decode_cbor(&json_to_cbor(raw)).expect("decode"),
+            ClientMessage::from_text(raw).expect("json")
+        );
+
+        let hello = r#"{"type":"hello_ok","session_id":1,"server_time_ms":2,
+            "limits":{"hi_capacity":1,"pnl_flush_ms":100,"max_positions_per_session":1},
+            "encoding":"cbor"}"#;
+        let msg = ServerMessage::decode_cbor(&json_to_cbor(hello)).expect("decode");
+        let ServerMessage::HelloOk { encoding, .. } = msg else {
+            panic!("expected HelloOk");
+        };
+        assert_eq!(encoding, Some(crate::Encoding::Cbor));
+    }
+}
@@ -0,0 +1,483 @@
+//! Delta-encoded PnL updates.
+//!
+//! With [`Capability::PnlDelta`](crate::Capability::PnlDelta) negotiated, the
+//! server may replace a `PnlUpdate` with a `PnlDelta` carrying only the fields
+//! that changed since the previous update for the same position. Each delta
+//! names the `server_time_ms` of the update it applies to, so a lost or
+//! reordered frame is detected instead of silently producing wrong values.
+//!
+//! A field that becomes unknown cannot be expressed as a delta; the server
+//! sends a full `PnlUpdate` instead, which also resets the base.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{PnlUpdateRef, ServerMessage, ServerMessageRef};
+
+/// Error returned when a `PnlDelta` cannot be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PnlDeltaError {
+    /// No full update has been seen for the position.
+    MissingBase {
+        /// Position the delta refers to.
+        position_id: u64,
+    },
+    /// The delta was computed against a different update than the last one
+    /// received, so a frame was lost or reordered.
+    OutOfOrder {
+        /// Position the delta refers to.
+        position_id: u64,
+        /// `server_time_ms` of the last update received.
+        expected: u64,
+        /// `base_server_time_ms` carried by the delta.
+        found: u64,
+    },
+}
+
+impl fmt::Display for PnlDeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PnlDeltaError::MissingBase { position_id } => {
+                write!(f, "pnl delta for position {position_id} has no base update")
+            }
+            PnlDeltaError::OutOfOrder {
+                position_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "pnl delta for position {position_id} applies to update at {found}, last update was at {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PnlDeltaError {}
+
+fn snapshot(msg: &ServerMessage) -> Option<PnlUpdateRef> {
+    match *msg {
+        ServerMessage::PnlUpdate {
+            position_id,
+            profit_units,
+            proceeds_units,
+            server_time_ms,
+            token_price_quote,
+            market_cap_quote,
+            watched,
+        } => Some(PnlUpdateRef {
+            position_id,
+            profit_units,
+            proceeds_units,
+            server_time_ms,
+            token_price_quote,
+            market_cap_quote,
+            watched,
+        }),
+        _ => None,
+    }
+}
+
+/// Reconstructs full `PnlUpdate` messages from a stream containing deltas.
+#[derive(Debug, Clone, Default)]
+pub struct PnlDeltaDecoder {
+    last: HashMap<u64, PnlUpdateRef>,
+}
+
+impl PnlDeltaDecoder {
+    /// Creates a decoder with no known positions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last full update reconstructed for a position.
+    pub fn last(&self, position_id: u64) -> Option<&PnlUpdateRef> {
+        self.last.get(&position_id)
+    }
+
+    /// Drops the base of one position.
+    pub fn forget(&mut self, position_id: u64) {
+        self.last.remove(&position_id);
+    }
+
+    /// Drops every base, e.g. after reconnecting without resume.
+    pub fn clear(&mut self) {
+        self.last.clear();
+    }
+
+    /// Feeds a server message and returns it with deltas expanded.
+    ///
+    /// `PnlDelta` becomes `PnlUpdate`, batches are decoded message by
+    /// message, and other messages pass through unchanged. `PositionClosed`
+    /// and `HelloOk` drop the bases they make obsolete.
+    ///
+    /// A batch is decoded as a unit: if any message in it fails, no base
+    /// changes.
+    pub fn decode(&mut self, msg: ServerMessage) -> Result<ServerMessage, PnlDeltaError> {
+        if !matches!(msg, ServerMessage::Batch { .. }) {
+            return decode_with(&mut self.last, msg);
+        }
+        let mut staged = Staged {
+            committed: &self.last,
+            changes: HashMap::new(),
+            cleared: false,
+        };
+        let decoded = decode_with(&mut staged, msg)?;
+        let Staged {
+            changes, cleared, ..
+        } = staged;
+        if cleared {
+            self.last.clear();
+        }
+        for (position_id, update) in changes {
+            match update {
+                Some(update) => self.last.insert(position_id, update),
+                None => self.last.remove(&position_id),
+            };
+        }
+        Ok(decoded)
+    }
+}
+
+/// Per-position bases a message is decoded against.
+trait Bases {
+    fn get(&self, position_id: u64) -> Option<PnlUpdateRef>;
+    fn insert(&mut self, update: PnlUpdateRef);
+    fn remove(&mut self, position_id: u64);
+    fn clear(&mut self);
+}
+
+impl Bases for HashMap<u64, PnlUpdateRef> {
+    fn get(&self, position_id: u64) -> Option<PnlUpdateRef> {
+        HashMap::get(self, &position_id).copied()
+    }
+
+    fn insert(&mut self, update: PnlUpdateRef) {
+        HashMap::insert(self, update.position_id, update);
+    }
+
+    fn remove(&mut self, position_id: u64) {
+        HashMap::remove(self, &position_id);
+    }
+
+    fn clear(&mut self) {
+        HashMap::clear(self);
+    }
+}
+
+/// Changes a batch makes to the bases, held back until it fully decodes.
+struct Staged<'a> {
+    committed: &'a HashMap<u64, PnlUpdateRef>,
+    /// New base per touched position; `None` once dropped.
+    changes: HashMap<u64, Option<PnlUpdateRef>>,
+    /// Whether the committed bases were dropped before `changes`.
+    cleared: bool,
+}
+
+impl Bases for Staged<'_> {
+    fn get(&self, position_id: u64) -> Option<PnlUpdateRef> {
+        match self.changes.get(&position_id) {
+            Some(update) => *update,
+            None if self.cleared => None,
+            None => self.committed.get(&position_id).copied(),
+        }
+    }
+
+    fn insert(&mut self, update: PnlUpdateRef) {
+        self.changes.insert(update.position_id, Some(update));
+    }
+
+    fn remove(&mut self, position_id: u64) {
+        self.changes.insert(position_id, None);
+    }
+
+    fn clear(&mut self) {
+        self.changes.clear();
+        self.cleared = true;
+    }
+}
+
+fn decode_with(bases: &mut impl Bases, msg: ServerMessage) -> Result<ServerMessage, PnlDeltaError> {
+    match msg {
+        ServerMessage::PnlDelta {
+            position_id,
+            base_server_time_ms,
+            server_time_ms,
+            profit_units,
+            proceeds_units,
+            token_price_quote,
+            market_cap_quote,
+        } => {
+            let mut base = bases
+                .get(position_id)
+                .ok_or(PnlDeltaError::MissingBase { position_id })?;
+            if base.server_time_ms != base_server_time_ms {
+                return Err(PnlDeltaError::OutOfOrder {
+                    position_id,
+                    expected: base.server_time_ms,
+                    found: base_server_time_ms,
+                });
+            }
+            base.server_time_ms = server_time_ms;
+            base.profit_units = profit_units.unwrap_or(base.profit_units);
+            base.proceeds_units = proceeds_units.unwrap_or(base.proceeds_units);
+            base.token_price_quote = token_price_quote.or(base.token_price_quote);
+            base.market_cap_quote = market_cap_quote.or(base.market_cap_quote);
+            bases.insert(base);
+            Ok(ServerMessageRef::PnlUpdate(base).into_owned())
+        }
+        ServerMessage::Batch { messages } => Ok(ServerMessage::Batch {
+            messages: messages
+                .into_iter()
+                .map(|msg| decode_with(bases, msg))
+                .collect::<Result<_, _>>()?,
+        }),
+        msg => {
+            if let Some(update) = snapshot(&msg) {
+                bases.insert(update);
+                return Ok(msg);
+            }
+            match msg {
+                ServerMessage::PositionClosed { position_id, .. } => bases.remove(position_id),
+                ServerMessage::HelloOk { .. } => bases.clear(),
+                _ => {}
+            }
+            Ok(msg)
+        }
+    }
+}
+
+/// Replaces `PnlUpdate` messages with `PnlDelta` where possible.
+///
+/// The server-side counterpart of [`PnlDeltaDecoder`], for test servers.
+#[derive(Debug, Clone, Default)]
+pub struct PnlDeltaEncoder {
+    last: HashMap<u64, PnlUpdateRef>,
+}
+
+impl PnlDeltaEncoder {
+    /// Creates an encoder with no known positions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds an outgoing message and returns what to send instead.
+    pub fn encode(&mut self, msg: ServerMessage) -> ServerMessage {
+        if let Some(next) = snapshot(&msg) {
+            return self.encode_update(msg, next);
+        }
+        match msg {
+            ServerMessage::Batch { messages } => ServerMessage::Batch {
+                messages: messages.into_iter().map(|msg| self.encode(msg)).collect(),
+            },
+            ServerMessage::PositionClosed { position_id, .. } => {
+                self.last.remove(&position_id);
+                msg
+            }
+            ServerMessage::HelloOk { .. } => {
+                self.last.clear();
+                msg
+            }
+            msg => msg,
+        }
+    }
+
+    fn encode_update(&mut self, msg: ServerMessage, next: PnlUpdateRef) -> ServerMessage {
+        let Some(base) = self.last.insert(next.position_id, next) else {
+            return msg;
+        };
+        let lost = |old: Option<u64>, new: Option<u64>| old.is_some() && new.is_none();
+        if base.watched != next.watched
+            || lost(base.token_price_quote, next.token_price_quote)
+            || lost(base.market_cap_quote, next.market_cap_quote)
+        {
+            return msg;
+        }
+        fn changed<T: PartialEq>(old: T, new: T) -> Option<T> {
+            (old != new).then_some(new)
+        }
+        ServerMessage::PnlDelta {
+            position_id: next.position_id,
+            base_server_time_ms: base.server_time_ms,
+            server_time_ms: next.server_time_ms,
+            profit_units: changed(base.profit_units, next.profit_units),
+            proceeds_units: changed(base.proceeds_units, next.proceeds_units),
+            token_price_quote: changed(base.token_price_quote, next.token_price_quote).flatten(),
+            market_cap_quote: changed(base.market_cap_quote, next.market_cap_quote).flatten(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pnl(server_time_ms: u64, profit_units: i64, price: Option<u64>) -> ServerMessage {
+        ServerMessage::PnlUpdate {
+            position_id: 1,
+            profit_units,
+            proceeds_units: 1_000,
+            server_time_ms,
+            token_price_quote: price,
+            market_cap_quote: Some(50_000),
+            watched: false,
+        }
+    }
+
+    #[test]
+    fn encoder_and_decoder_round_trip() {
+        let updates = vec![
+            pnl(100, 5, Some(10)),
+            pnl(200, 7, Some(10)),
+            pnl(300, 7, Some(11)),
+            pnl(400, 9, None),
+            pnl(500, 9, Some(12)),
+        ];
+        let mut encoder = PnlDeltaEncoder::new();
+        let mut decoder = PnlDeltaDecoder::new();
+        let sent: Vec<ServerMessage> = updates.iter().cloned().map(|m| encoder.encode(m)).collect();
+
+        assert!(matches!(sent[0], ServerMessage::PnlUpdate { .. }));
+        assert_eq!(
+            sent[1],
+            ServerMessage::PnlDelta {
+                position_id: 1,
+                base_server_time_ms: 100,
+                server_time_ms: 200,
+                profit_units: Some(7),
+                proceeds_units: None,
+                token_price_quote: None,
+                market_cap_quote: None,
+            }
+        );
+        // Losing the price cannot be expressed as a delta.
+        assert!(matches!(sent[3], ServerMessage::PnlUpdate { .. }));
+
+        let received: Vec<ServerMessage> = sent
+            .into_iter()
+            .map(|m| decoder.decode(m).expect("decode"))
+            .collect();
+        assert_eq!(received, updates);
+        assert_eq!(decoder.last(1).map(|p| p.server_time_ms), Some(500));
+    }
+
+    #[test]
+    fn decoder_detects_missing_base_and_reordering() {
+        let delta = |base_server_time_ms, server_time_ms| ServerMessage::PnlDelta {
+            position_id: 1,
+            base_server_time_ms,
+            server_time_ms,
+            profit_units: Some(1),
+            proceeds_units: None,
+            token_price_quote: None,
+            market_cap_quote: None,
+        };
+        let mut decoder = PnlDeltaDecoder::new();
+        assert_eq!(
+            decoder.decode(delta(100, 200)),
+            Err(PnlDeltaError::MissingBase { position_id: 1 })
+        );
+
+        decoder.decode(pnl(100, 5, Some(10))).expect("full update");
+        assert_eq!(
+            decoder.decode(delta(200, 300)),
+            Err(PnlDeltaError::OutOfOrder {
+                position_id: 1,
+                expected: 100,
+                found: 200,
+            })
+        );
+        assert_eq!(decoder.decode(delta(100, 200)), Ok(pnl(200, 1, Some(10))));
+    }
+
+    #[test]
+    fn decodes_batches_and_forgets_closed_positions() {
+        let mut decoder = PnlDeltaDecoder::new();
+        let mut encoder = PnlDeltaEncoder::new();
+        let batch = ServerMessage::Batch {
+            messages: vec![pnl(100, 5, Some(10)), pnl(200, 6, Some(10))],
+        };
+        let sent = encoder.encode(batch.clone());
+        assert_eq!(decoder.decode(sent), Ok(batch));
+
+        let closed = ServerMessage::PositionClosed {
+            position_id: 1,
+            wallet_pubkey: crate::Pubkey58::from_bytes(&[1; 32]),
+            mint: crate::Pubkey58::from_bytes(&[2; 32]),
+            token_account: None,
+            reason: crate::ExitReason::Manual,
+            mirror_source: None,
+            slot: 1,
+            watched: false,
+        };
+        decoder.decode(closed).expect("pass through");
+        assert_eq!(decoder.last(1), None);
+    }
+
+    #[test]
+    fn failed_batch_leaves_bases_unchanged() {
+        let mut decoder = PnlDeltaDecoder::new();
+        let mut encoder = PnlDeltaEncoder::new();
+        decoder
+            .decode(encoder.encode(pnl(100, 5, Some(10))))
+            .expect("full update");
+
+        let mut messages = match encoder.encode(ServerMessage::Batch {
+            messages: vec![pnl(200, 6, Some(10)), pnl(300, 7, Some(10))],
+        }) {
+            ServerMessage::Batch { messages } => messages,
+            other => panic!("expected batch, got {other:?}"),
+        };
+        let good = messages.remove(0);
+        let bad = ServerMessage::PnlDelta {
+            position_id: 1,
+            base_server_time_ms: 150,
+            server_time_ms: 300,
+            profit_units: Some(7),
+            proceeds_units: None,
+            token_price_quote: None,
+            market_cap_quote: None,
+        };
+        let batch = ServerMessage::Batch {
+            messages: vec![good.clone(), bad],
+        };
+        assert_eq!(
+            decoder.decode(batch),
+            Err(PnlDeltaError::OutOfOrder {
+                position_id: 1,
+                expected: 200,
+                found: 150,
+            })
+        );
+        assert_eq!(decoder.last(1).map(|p| p.server_time_ms), Some(100));
+
+        // The first delta still applies once the batch is retried or resent.
+        assert_eq!(decoder.decode(good), Ok(pnl(200, 6, Some(10))));
+    }
+
+    #[test]
+    fn failed_batch_rolls_back_staged_resets() {
+        let mut decoder = PnlDeltaDecoder::new();
+        decoder.decode(pnl(100, 5, Some(10))).expect("full update");
+        let hello_ok = crate::fixtures::server_messages()
+            .into_iter()
+            .find(|msg| matches!(msg, ServerMessage::HelloOk { .. }))
+            .expect("hello_ok fixture");
+        let delta = ServerMessage::PnlDelta {
+            position_id: 1,
+            base_server_time_ms: 100,
+            server_time_ms: 200,
+            profit_units: Some(6),
+            proceeds_units: None,
+            token_price_quote: None,
+            market_cap_quote: None,
+        };
+        let batch = ServerMessage::Batch {
+            messages: vec![hello_ok, delta.clone()],
+        };
+        assert_eq!(
+            decoder.decode(batch),
+            Err(PnlDeltaError::MissingBase { position_id: 1 })
+        );
+        assert_eq!(decoder.decode(delta), Ok(pnl(200, 6, Some(10))));
+    }
+}
@@ -0,0 +1,291 @@
+//! One sample of every message variant, shared by the codec tests.
+
+use crate::*;
+
+fn key(n: u8) -> Pubkey58 {
+    Pubkey58::from_bytes(&[n; 32])
+}
+
+fn strategy() -> StrategyConfigMsg {
+    StrategyConfigMsg {
+        target_profit_pct: 0.0,
+        stop_loss_pct: 5.0,
+        trailing_stop_pct: 10.0,
+        sell_on_graduation: true,
+        take_profit_levels: vec![TakeProfitLevelMsg {
+            profit_pct: 50.0,
+            sell_pct: 30.0,
+            trailing_stop_pct: 5.0,
+        }],
+        liquidity_guard: true,
+        breakeven_trail_pct: 20.0,
+    }
+}
+
+fn market_context() -> MarketContextMsg {
+    MarketContextMsg {
+        market_type: MarketTypeMsg::PumpSwap,
+        pumpfun: None,
+        pumpswap: Some(PumpSwapContextMsg {
+            pool: key(7),
+            global_config: Some(key(8)),
+        }),
+        meteora_dbc: None,
+        meteora_damm_v2: None,
+        raydium_launchpad: None,
+        raydium_cpmm: None,
+    }
+}
+
+fn limits() -> LimitsMsg {
+    LimitsMsg {
+        hi_capacity: 256,
+        pnl_flush_ms: 100,
+        max_positions_per_session: 256,
+        max_wallets_per_session: 8,
+        max_positions_per_wallet: 64,
+        max_sessions_per_api_key: 1,
+        max_watch_wallets_per_session: 10,
+    }
+}
+
+/// Every `ClientMessage` variant, with optional fields populated.
+pub(crate) fn client_messages() -> Vec<ClientMessage> {
+    vec![
+        ClientMessage::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: Some("bot/0.1".to_string()),
+            capabilities: Capability::SUPPORTED.to_vec(),
+            encodings: vec![Encoding::Cbor, Encoding::Json],
+            request_id: Some(1),
+        },
+        ClientMessage::Ping {
+            client_time_ms: 1_700_000_000_000,
+            request_id: None,
+        },
+        ClientMessage::Configure {
+            wallet_pubkeys: vec![key(1), key(2)],
+            strategy: strategy(),
+            send_mode: Some(SendMode::HeliusSender),
+            tip_lamports: Some(1_000),
+            watch_wallets: vec![WatchWalletEntryMsg {
+                pubkey: key(3),
+                auto_buy: Some(AutoBuyConfigMsg {
+                    wallet_pubkey: key(1),
+                    amount_quote_units: 500_000,
+                    amount_usd1_units: Some(2_000_000),
+                }),
+                mirror_sell: true,
+            }],
+            mirror_config: Some(MirrorConfigMsg {
+                max_positions_per_wallet: 2,
+                cooldown_sec: 30,
+                skip_creator_tokens: true,
+                max_active_sol: 1.5,
+                buy_slippage_bps: 300,
+                min_liquidity_sol: Some(10.0),
+                max_entry_drift_pct: None,
+                max_consecutive_losses: Some(4),
+            }),
+            request_id: Some(2),
+        },
+        ClientMessage::UpdateStrategy {
+            strategy: strategy(),
+            request_id: None,
+        },
+        ClientMessage::ClosePosition {
+            position_id: Some(5),
+            token_account: None,
+            request_id: Some(3),
+        },
+        ClientMessage::RequestExitSignal {
+            position_id: None,
+            token_account: Some(key(4)),
+            slippage_bps: Some(150),
+            request_id: None,
+        },
+        ClientMessage::UpdateWallets {
+            wallet_pubkeys: vec![key(1)],
+            request_id: None,
+        },
+        ClientMessage::UpdateWatchWallets {
+            watch_wallets: vec![WatchWalletEntryMsg {
+                pubkey: key(3),
+                auto_buy: None,
+                mirror_sell: false,
+            }],
+            request_id: Some(4),
+        },
+        ClientMessage::UpdatePositionStrategy {
+            position_id: 5,
+            strategy: StrategyConfigMsg::default(),
+            request_id: None,
+        },
+        ClientMessage::Resume {
+            session_id: 9,
+            last_seq: 120,
+            request_id: None,
+        },
+        ClientMessage::MirrorBuyResult {
+            mint: key(6),
+            success: false,
+            request_id: None,
+        },
+    ]
+}
+
+/// Every `ServerMessage` variant, with optional fields populated.
+pub(crate) fn server_messages() -> Vec<ServerMessage> {
+    vec![
+        ServerMessage::HelloOk {
+            session_id: 9,
+            server_time_ms: 1_700_000_000_000,
+            limits: limits(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: vec![Capability::TradeTicks, Capability::Sequenced],
+            encoding: Some(Encoding::MessagePack),
+        },
+        ServerMessage::Pong {
+            server_time_ms: 1_700_000_000_001,
+        },
+        ServerMessage::Ack { request_id: 2 },
+        ServerMessage::Error {
+            code: ErrorCode::Unknown("new_code".to_string()),
+            message: "future".to_string(),
+            request_id: Some(3),
+        },
+        ServerMessage::PnlUpdate {
+            position_id: 5,
+            profit_units: -12,
+            proceeds_units: 34,
+            server_time_ms: 999,
+            token_price_quote: Some(24_000),
+            market_cap_quote: None,
+            watched: true,
+        },
+        ServerMessage::PnlDelta {
+            position_id: 5,
+            base_server_time_ms: 999,
+            server_time_ms: 1_099,
+            profit_units: Some(-10),
+            proceeds_units: None,
+            token_price_quote: Some(24_100),
+            market_cap_quote: None,
+        },
+        ServerMessage::LiquiditySnapshot {
+            position_id: 5,
+            bands: vec![SlippageBandMsg {
+                slippage_bps: 100,
+                max_tokens: 5_000,
+                coverage_pct: 50.0,
+            }],
+            liquidity_trend: LiquidityTrend::Draining,
+            server_time_ms: 999,
+            watched: false,
+        },
+        ServerMessage::TradeTick {
+            position_id: 5,
+            time_ms: 1_700_000_000_123,
+            side: TradeSide::Sell,
+            token_amount: 50_000_000,
+            quote_amount: 1_200_000_000,
+            price_quote: 24_000,
+            maker: Some(key(1)),
+            tx_signature: Some(Signature58::from_bytes(&[2; 64])),
+            watched: false,
+        },
+        ServerMessage::BalanceUpdate {
+            wallet_pubkey: key(1),
+            mint: key(6),
+            token_account: Some(key(4)),
+            token_program: None,
+            tokens: 1_000,
+            slot: 42,
+        },
+        ServerMessage::PositionOpened {
+            position_id: 5,
+            wallet_pubkey: key(1),
+            mint: key(6),
+            token_account: key(4),
+            token_program: Some(key(5)),
+            tokens: 1_000,
+            entry_quote_units: 2_000,
+            market_context: Some(market_context()),
+            token_name: Some("Token".to_string()),
+            token_symbol: Some("TOK".to_string()),
+            token_decimals: Some(6),
+            token_price_quote: Some(2),
+            market_cap_quote: Some(2_000_000),
+            pool_liquidity_quote: Some(30_000),
+            opened_at_ms: Some(1_700_000_000_000),
+            slot: 40,
+            watched: false,
+            mirror_source: Some("watch".to_string()),
+        },
+        ServerMessage::PositionClosed {
+            position_id: 5,
+            wallet_pubkey: key(1),
+            mint: key(6),
+            token_account: None,
+            reason: ExitReason::Manual,
+            mirror_source: None,
+            slot: 50,
+            watched: true,
+        },
+        ServerMessage::ExitSignalWithTx {
+            session_id: 9,
+            position_id: 5,
+            wallet_pubkey: key(1),
+            mint: key(6),
+            token_account: Some(key(4)),
+            token_program: None,
+            position_tokens: 1_000,
+            profit_units: 50,
+            reason: ExitReason::ChainedTakeProfit,
+            triggered_at_ms: 1_700_000_000_500,
+            market_context: Some(market_context()),
+            unsigned_tx_b64: "dGVzdA==".to_string(),
+            sell_tokens: Some(300),
+            level_index: Some(0),
+            mirror_source: None,
+            watched: false,
+        },
+        ServerMessage::MirrorBuySignal {
+            session_id: 9,
+            watched_wallet: key(3),
+            mint: key(6),
+            user_wallet: key(1),
+            amount_quote_units: 500_000,
+            input: QuoteAsset::Usd1,
+            unsigned_tx_b64: "dGVzdA==".to_string(),
+            slippage_bps: 300,
+            send_mode: Some(SendMode::Other("jito".to_string())),
+            tip_lamports: Some(1_000),
+            market_context: None,
+        },
+        ServerMessage::MirrorBuyFailed {
+            watched_wallet: key(3),
+            mint: key(6),
+            reason: "insufficient funds".to_string(),
+        },
+        ServerMessage::MirrorWalletAutoDisabled {
+            watched_wallet: key(3),
+            reason: "loss streak".to_string(),
+            loss_count: 3,
+        },
+        ServerMessage::Batch {
+            messages: vec![
+                ServerMessage::PnlUpdate {
+                    position_id: 5,
+                    profit_units: 10,
+                    proceeds_units: 2_010,
+                    server_time_ms: 1_000,
+                    token_price_quote: None,
+                    market_cap_quote: None,
+                    watched: false,
+                },
+                ServerMessage::Ack { request_id: 4 },
+            ],
+        },
+    ]
+}
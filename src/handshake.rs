@@ -0,0 +1,199 @@
+//! Protocol version and capability negotiation.
+//!
+//! Clients announce their version and capabilities with `ClientMessage::Hello`
+//! and servers answer in `ServerMessage::HelloOk`. Peers that predate the
+//! handshake are treated as [`MIN_PROTOCOL_VERSION`] with no capabilities.
+//!
+//! The handshake also carries an encoding hint: the client lists the
+//! [`Encoding`]s it can read and the server names the one it will use for the
+//! frames that follow `HelloOk`. Both sides fall back to JSON text.
+
+use std::fmt;
+
+/// Protocol version implemented by this crate.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Oldest protocol version this crate can talk to.
+///
+/// Version 1 is the protocol before version negotiation existed.
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+pub(crate) fn legacy_protocol_version() -> u32 {
+    MIN_PROTOCOL_VERSION
+}
+
+open_enum! {
+    /// Optional protocol feature a peer understands.
+    pub enum Capability {
+        /// Capability not known to this version of the crate.
+        Other(String),
+        /// Multi-level `take_profit_levels` in strategies.
+        TakeProfitLevels => "take_profit_levels",
+        /// `mirror_config` and watched-wallet mirror trading.
+        MirrorConfig => "mirror_config",
+        /// `TradeTick` events.
+        TradeTicks => "trade_ticks",
+        /// `LiquiditySnapshot` events.
+        LiquiditySnapshots => "liquidity_snapshots",
+        /// Per-position `UpdatePositionStrategy` overrides.
+        PositionStrategy => "position_strategy",
+        /// `request_id` correlation with `Ack` responses.
+        RequestIds => "request_ids",
+        /// Sequenced `ServerEnvelope` frames and `Resume`.
+        Sequenced => "sequenced",
+        /// `ServerMessage::Batch` frames.
+        Batch => "batch",
+        /// `ServerMessage::PnlDelta` updates.
+        PnlDelta => "pnl_delta",
+    }
+}
+
+impl Capability {
+    /// Capabilities implemented by this version of the crate.
+    pub const SUPPORTED: &'static [Capability] = &[
+        Capability::TakeProfitLevels,
+        Capability::MirrorConfig,
+        Capability::TradeTicks,
+        Capability::LiquiditySnapshots,
+        Capability::PositionStrategy,
+        Capability::RequestIds,
+        Capability::Sequenced,
+        Capability::Batch,
+        Capability::PnlDelta,
+    ];
+}
+
+open_enum! {
+    /// Frame encoding a peer can read.
+    pub enum Encoding {
+        /// Encoding not known to this version of the crate.
+        Other(String),
+        /// JSON text frames.
+        Json => "json",
+        /// MessagePack binary frames (`msgpack` feature).
+        MessagePack => "msgpack",
+        /// CBOR binary frames (`cbor` feature).
+        Cbor => "cbor",
+    }
+}
+
+impl Encoding {
+    /// True if this build of the crate can encode and decode `self`.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Encoding::Json => true,
+            Encoding::MessagePack => cfg!(feature = "msgpack"),
+            Encoding::Cbor => cfg!(feature = "cbor"),
+            Encoding::Other(_) => false,
+        }
+    }
+
+    /// Encodings supported by this build, binary ones first.
+    pub fn supported() -> Vec<Encoding> {
+        [Encoding::Cbor, Encoding::MessagePack, Encoding::Json]
+            .into_iter()
+            .filter(Encoding::is_supported)
+            .collect()
+    }
+}
+
+/// Picks the first encoding in the peer's preference list that this build
+/// supports, falling back to JSON.
+pub fn negotiate_encoding(offered: &[Encoding]) -> Encoding {
+    offered
+        .iter()
+        .find(|encoding| encoding.is_supported())
+        .cloned()
+        .unwrap_or(Encoding::Json)
+}
+
+/// Returns the capabilities present in both sets, in the order of `ours`.
+pub fn common_capabilities(ours: &[Capability], theirs: &[Capability]) -> Vec<Capability> {
+    ours.iter()
+        .filter(|cap| theirs.contains(cap))
+        .cloned()
+        .collect()
+}
+
+/// Error returned when a peer's protocol version cannot be served.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolVersionError {
+    /// Version announced by the peer.
+    pub peer: u32,
+}
+
+impl fmt::Display for ProtocolVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "protocol version {} is not supported (supported {}..={})",
+            self.peer, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+        )
+    }
+}
+
+impl std::error::Error for ProtocolVersionError {}
+
+/// Checks a peer's protocol version and returns the version both sides speak.
+///
+/// Peers newer than this crate are accepted and spoken to at
+/// [`PROTOCOL_VERSION`]; peers older than [`MIN_PROTOCOL_VERSION`] are rejected.
+pub fn negotiate_protocol_version(peer: u32) -> Result<u32, ProtocolVersionError> {
+    if peer < MIN_PROTOCOL_VERSION {
+        return Err(ProtocolVersionError { peer });
+    }
+    Ok(peer.min(PROTOCOL_VERSION))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_lowest_common_version() {
+        assert_eq!(negotiate_protocol_version(1), Ok(1));
+        assert_eq!(
+            negotiate_protocol_version(PROTOCOL_VERSION),
+            Ok(PROTOCOL_VERSION)
+        );
+        assert_eq!(
+            negotiate_protocol_version(PROTOCOL_VERSION + 5),
+            Ok(PROTOCOL_VERSION)
+        );
+        assert_eq!(
+            negotiate_protocol_version(0),
+            Err(ProtocolVersionError { peer: 0 })
+        );
+    }
+
+    #[test]
+    fn intersects_capabilities() {
+        let theirs = vec![
+            Capability::TradeTicks,
+            Capability::Other("quantum_sells".to_string()),
+            Capability::TakeProfitLevels,
+        ];
+        assert_eq!(
+            common_capabilities(Capability::SUPPORTED, &theirs),
+            vec![Capability::TakeProfitLevels, Capability::TradeTicks]
+        );
+    }
+
+    #[test]
+    fn negotiates_first_supported_encoding() {
+        let offered = vec![
+            Encoding::Other("protobuf".to_string()),
+            Encoding::Cbor,
+            Encoding::Json,
+        ];
+        let expected = if cfg!(feature = "cbor") {
+            Encoding::Cbor
+        } else {
+            Encoding::Json
+        };
+        assert_eq!(negotiate_encoding(&offered), expected);
+        assert_eq!(negotiate_encoding(&[]), Encoding::Json);
+        assert_eq!(Encoding::supported().last(), Some(&Encoding::Json));
+        assert_eq!(Encoding::from("msgpack"), Encoding::MessagePack);
+    }
+}
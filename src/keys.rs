@@ -0,0 +1,263 @@
+//! Base58-encoded Solana pubkey and signature wire types.
+//!
+//! Both types keep the original string so they serialize back unchanged.
+//! Deserialization accepts any string unless the `strict-keys` feature is
+//! enabled, in which case the value must decode to the expected byte length.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Error returned when a string is not a valid base58 key or signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyError {
+    /// The string contains characters outside the base58 alphabet.
+    InvalidBase58,
+    /// The string decodes to the wrong number of bytes.
+    InvalidLength {
+        /// Expected decoded length in bytes.
+        expected: usize,
+        /// Actual decoded length in bytes.
+        actual: usize,
+    },
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::InvalidBase58 => f.write_str("invalid base58 string"),
+            KeyError::InvalidLength { expected, actual } => {
+                write!(f, "expected {expected} decoded bytes, got {actual}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+fn decode<const N: usize>(s: &str) -> Result<[u8; N], KeyError> {
+    let bytes = bs58::decode(s)
+        .into_vec()
+        .map_err(|_| KeyError::InvalidBase58)?;
+    let actual = bytes.len();
+    bytes.try_into().map_err(|_| KeyError::InvalidLength {
+        expected: N,
+        actual,
+    })
+}
+
+macro_rules! base58_type {
+    ($(#[$meta:meta])* $name:ident, $len:expr, $max_chars:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(String);
+
+        impl $name {
+            /// Decoded length in bytes.
+            pub const LEN: usize = $len;
+
+            /// Parses and validates a base58 string.
+            pub fn parse(s: impl Into<String>) -> Result<Self, KeyError> {
+                let s = s.into();
+                decode::<$len>(&s)?;
+                Ok(Self(s))
+            }
+
+            /// Wraps a string without validating it.
+            pub fn new_unchecked(s: impl Into<String>) -> Self {
+                Self(s.into())
+            }
+
+            /// Encodes raw bytes as base58.
+            pub fn from_bytes(bytes: &[u8; $len]) -> Self {
+                Self(bs58::encode(bytes).into_string())
+            }
+
+            /// Decodes the raw bytes.
+            ///
+            /// Only fails for values accepted without validation.
+            pub fn to_bytes(&self) -> Result<[u8; $len], KeyError> {
+                decode::<$len>(&self.0)
+            }
+
+            /// Returns true when the value is valid base58 of the expected length.
+            pub fn is_valid(&self) -> bool {
+                self.to_bytes().is_ok()
+            }
+
+            /// Returns the base58 string.
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Consumes the value and returns the base58 string.
+            pub fn into_string(self) -> String {
+                self.0
+            }
+
+            /// Checks a wire string using the active validation policy.
+            ///
+            /// This is a no-op unless the `strict-keys` feature is enabled.
+            #[allow(unused_variables)]
+            pub(crate) fn check_wire(s: &str) -> Result<(), KeyError> {
+                #[cfg(feature = "strict-keys")]
+                decode::<$len>(s)?;
+                Ok(())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = KeyError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::parse(s)
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                Self::check_wire(&s).map_err(serde::de::Error::custom)?;
+                Ok(Self(s))
+            }
+        }
+
+        #[cfg(feature = "schema")]
+        impl schemars::JsonSchema for $name {
+            fn schema_name() -> std::borrow::Cow<'static, str> {
+                stringify!($name).into()
+            }
+
+            fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+                schemars::json_schema!({
+                    "type": "string",
+                    "description": concat!("Base58 encoding of ", stringify!($len), " bytes."),
+                    "pattern": "^[1-9A-HJ-NP-Za-km-z]+$",
+                    "maxLength": $max_chars
+                })
+            }
+        }
+    };
+}
+
+base58_type!(
+    /// Base58-encoded 32-byte Solana account pubkey.
+    Pubkey58,
+    32,
+    44
+);
+
+base58_type!(
+    /// Base58-encoded 64-byte Solana transaction signature.
+    Signature58,
+    64,
+    88
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYSTEM_PROGRAM: &str = "11111111111111111111111111111111";
+    const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+    #[test]
+    fn pubkey_parses_and_exposes_bytes() {
+        assert_eq!(
+            Pubkey58::parse(SYSTEM_PROGRAM).unwrap().to_bytes(),
+            Ok([0; 32])
+        );
+
+        let key: Pubkey58 = TOKEN_PROGRAM.parse().expect("valid pubkey");
+        let bytes = key.to_bytes().expect("bytes");
+        assert_eq!(Pubkey58::from_bytes(&bytes), key);
+        assert_eq!(key, TOKEN_PROGRAM);
+    }
+
+    #[test]
+    fn pubkey_rejects_invalid_strings() {
+        assert_eq!(Pubkey58::parse("0OIl"), Err(KeyError::InvalidBase58));
+        assert_eq!(
+            Pubkey58::parse("22222222222222222222222222222222"),
+            Err(KeyError::InvalidLength {
+                expected: 32,
+                actual: 23,
+            })
+        );
+        assert!(!Pubkey58::new_unchecked("not-a-key").is_valid());
+    }
+
+    #[test]
+    fn signature_round_trips_bytes() {
+        let sig = Signature58::from_bytes(&[7; 64]);
+        assert_eq!(sig.to_bytes(), Ok([7; 64]));
+        assert!(matches!(
+            Signature58::parse(TOKEN_PROGRAM),
+            Err(KeyError::InvalidLength { expected: 64, .. })
+        ));
+    }
+
+    #[test]
+    fn serializes_identical_string() {
+        let json = format!("\"{TOKEN_PROGRAM}\"");
+        let key: Pubkey58 = serde_json::from_str(&json).expect("deserialize");
+        assert_eq!(serde_json::to_string(&key).expect("serialize"), json);
+    }
+
+    #[cfg(not(feature = "strict-keys"))]
+    #[test]
+    fn lenient_deserialize_accepts_any_string() {
+        let key: Pubkey58 = serde_json::from_str("\"YourWalletPubkey\"").expect("deserialize");
+        assert_eq!(key.as_str(), "YourWalletPubkey");
+    }
+
+    #[cfg(feature = "strict-keys")]
+    #[test]
+    fn strict_deserialize_rejects_invalid_keys() {
+        assert!(serde_json::from_str::<Pubkey58>("\"YourWalletPubkey\"").is_err());
+        assert!(serde_json::from_str::<Signature58>(&format!("\"{TOKEN_PROGRAM}\"")).is_err());
+    }
+}
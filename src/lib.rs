@@ -5,8 +5,53 @@
 
 use serde::{Deserialize, Deserializer, Serialize};
 
+#[macro_use]
+mod macros;
+mod batch;
+mod borrowed;
+mod builder;
+#[cfg(feature = "client")]
+pub mod client;
+mod clock;
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+mod codec;
+mod delta;
+#[cfg(test)]
+mod fixtures;
+mod handshake;
+mod keys;
+pub mod policy;
+pub mod record;
+#[cfg(feature = "schema")]
+pub mod schema;
+mod sequence;
+pub mod state;
+pub mod strategy;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "typescript")]
+pub mod typescript;
+
+pub use batch::{flatten_batches, FlattenBatches, IntoMessages};
+pub use borrowed::{LiquiditySnapshotRef, PnlUpdateRef, ServerMessageRef, TradeTickRef};
+pub use builder::{BuildError, ConfigureBuilder, StrategyBuilder};
+pub use clock::{ClockSample, ClockSync};
+pub use delta::{PnlDeltaDecoder, PnlDeltaEncoder, PnlDeltaError};
+pub use handshake::{
+    common_capabilities, negotiate_encoding, negotiate_protocol_version, Capability, Encoding,
+    ProtocolVersionError, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+pub use keys::{KeyError, Pubkey58, Signature58};
+pub use sequence::{SequenceEvent, SequenceTracker, ServerEnvelope, ServerFrame};
+
+/// Compiles the README example as a doctest.
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+struct ReadmeDoctests;
+
 /// Supported market types for an opened position.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum MarketTypeMsg {
     /// pump.fun market.
@@ -27,68 +72,75 @@ pub enum MarketTypeMsg {
 ///
 /// This is currently empty and acts as an explicit marker.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PumpFunContextMsg {}
 
 /// Context payload for `MarketTypeMsg::PumpSwap`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PumpSwapContextMsg {
     /// PumpSwap pool account.
-    pub pool: String,
+    pub pool: Pubkey58,
     /// Optional PumpSwap global config account.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub global_config: Option<String>,
+    pub global_config: Option<Pubkey58>,
 }
 
 /// Context payload for `MarketTypeMsg::MeteoraDbc`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MeteoraDbcContextMsg {
     /// Meteora DBC pool account.
-    pub pool: String,
+    pub pool: Pubkey58,
     /// Meteora DBC config account.
-    pub config: String,
+    pub config: Pubkey58,
     /// Quote mint used by the pool.
-    pub quote_mint: String,
+    pub quote_mint: Pubkey58,
 }
 
 /// Context payload for `MarketTypeMsg::MeteoraDammV2`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MeteoraDammV2ContextMsg {
     /// Meteora DAMM v2 pool account.
-    pub pool: String,
+    pub pool: Pubkey58,
 }
 
 /// Context payload for `MarketTypeMsg::RaydiumLaunchpad`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RaydiumLaunchpadContextMsg {
     /// Raydium Launchpad pool account.
-    pub pool: String,
+    pub pool: Pubkey58,
     /// Raydium Launchpad config account.
-    pub config: String,
+    pub config: Pubkey58,
     /// Raydium Launchpad platform account.
-    pub platform: String,
+    pub platform: Pubkey58,
     /// Quote mint used by the pool.
-    pub quote_mint: String,
+    pub quote_mint: Pubkey58,
     /// User's quote token account associated with the position.
-    pub user_quote_account: String,
+    pub user_quote_account: Pubkey58,
 }
 
 /// Context payload for `MarketTypeMsg::RaydiumCpmm`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RaydiumCpmmContextMsg {
     /// Raydium CPMM pool account.
-    pub pool: String,
+    pub pool: Pubkey58,
     /// Raydium CPMM config account.
-    pub config: String,
+    pub config: Pubkey58,
     /// Quote mint used by the pool.
-    pub quote_mint: String,
+    pub quote_mint: Pubkey58,
     /// User's quote token account associated with the position.
-    pub user_quote_account: String,
+    pub user_quote_account: Pubkey58,
 }
 
 /// Market-specific context carried with position events.
 ///
 /// Exactly one optional context field should match `market_type`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MarketContextMsg {
     /// Market discriminator for the active context field.
     pub market_type: MarketTypeMsg,
@@ -112,8 +164,146 @@ pub struct MarketContextMsg {
     pub raydium_cpmm: Option<RaydiumCpmmContextMsg>,
 }
 
+impl MarketContextMsg {
+    /// Returns the typed view of this context.
+    ///
+    /// Fails when the populated context field does not match `market_type`.
+    pub fn context(&self) -> Result<MarketContext, MarketContextError> {
+        MarketContext::try_from(self.clone())
+    }
+}
+
+/// Type-safe view of [`MarketContextMsg`] holding exactly one market context.
+///
+/// Serializes to the same JSON shape as [`MarketContextMsg`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(try_from = "MarketContextMsg", into = "MarketContextMsg")]
+pub enum MarketContext {
+    /// pump.fun market context.
+    PumpFun(PumpFunContextMsg),
+    /// PumpSwap market context.
+    PumpSwap(PumpSwapContextMsg),
+    /// Meteora Dynamic Bonding Curve market context.
+    MeteoraDbc(MeteoraDbcContextMsg),
+    /// Meteora DAMM v2 market context.
+    MeteoraDammV2(MeteoraDammV2ContextMsg),
+    /// Raydium Launchpad market context.
+    RaydiumLaunchpad(RaydiumLaunchpadContextMsg),
+    /// Raydium CPMM market context.
+    RaydiumCpmm(RaydiumCpmmContextMsg),
+}
+
+impl MarketContext {
+    /// Returns the market discriminator for this context.
+    pub fn market_type(&self) -> MarketTypeMsg {
+        match self {
+            MarketContext::PumpFun(_) => MarketTypeMsg::PumpFun,
+            MarketContext::PumpSwap(_) => MarketTypeMsg::PumpSwap,
+            MarketContext::MeteoraDbc(_) => MarketTypeMsg::MeteoraDbc,
+            MarketContext::MeteoraDammV2(_) => MarketTypeMsg::MeteoraDammV2,
+            MarketContext::RaydiumLaunchpad(_) => MarketTypeMsg::RaydiumLaunchpad,
+            MarketContext::RaydiumCpmm(_) => MarketTypeMsg::RaydiumCpmm,
+        }
+    }
+}
+
+/// Reasons a [`MarketContextMsg`] cannot be converted into a [`MarketContext`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketContextError {
+    /// No context field is populated for `market_type`.
+    Missing {
+        /// Declared market discriminator.
+        market_type: MarketTypeMsg,
+    },
+    /// A context field other than the one for `market_type` is populated.
+    Unexpected {
+        /// Declared market discriminator.
+        market_type: MarketTypeMsg,
+        /// Market of the extra populated context field.
+        found: MarketTypeMsg,
+    },
+}
+
+impl std::fmt::Display for MarketContextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarketContextError::Missing { market_type } => {
+                write!(f, "missing context for market type {market_type:?}")
+            }
+            MarketContextError::Unexpected { market_type, found } => write!(
+                f,
+                "unexpected {found:?} context for market type {market_type:?}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MarketContextError {}
+
+impl TryFrom<MarketContextMsg> for MarketContext {
+    type Error = MarketContextError;
+
+    fn try_from(msg: MarketContextMsg) -> Result<Self, Self::Error> {
+        let market_type = msg.market_type;
+        let mut found = Vec::with_capacity(1);
+        if let Some(ctx) = msg.pumpfun {
+            found.push(MarketContext::PumpFun(ctx));
+        }
+        if let Some(ctx) = msg.pumpswap {
+            found.push(MarketContext::PumpSwap(ctx));
+        }
+        if let Some(ctx) = msg.meteora_dbc {
+            found.push(MarketContext::MeteoraDbc(ctx));
+        }
+        if let Some(ctx) = msg.meteora_damm_v2 {
+            found.push(MarketContext::MeteoraDammV2(ctx));
+        }
+        if let Some(ctx) = msg.raydium_launchpad {
+            found.push(MarketContext::RaydiumLaunchpad(ctx));
+        }
+        if let Some(ctx) = msg.raydium_cpmm {
+            found.push(MarketContext::RaydiumCpmm(ctx));
+        }
+
+        if let Some(extra) = found.iter().find(|ctx| ctx.market_type() != market_type) {
+            return Err(MarketContextError::Unexpected {
+                market_type,
+                found: extra.market_type(),
+            });
+        }
+        found
+            .pop()
+            .ok_or(MarketContextError::Missing { market_type })
+    }
+}
+
+impl From<MarketContext> for MarketContextMsg {
+    fn from(ctx: MarketContext) -> Self {
+        let mut msg = MarketContextMsg {
+            market_type: ctx.market_type(),
+            pumpfun: None,
+            pumpswap: None,
+            meteora_dbc: None,
+            meteora_damm_v2: None,
+            raydium_launchpad: None,
+            raydium_cpmm: None,
+        };
+        match ctx {
+            MarketContext::PumpFun(ctx) => msg.pumpfun = Some(ctx),
+            MarketContext::PumpSwap(ctx) => msg.pumpswap = Some(ctx),
+            MarketContext::MeteoraDbc(ctx) => msg.meteora_dbc = Some(ctx),
+            MarketContext::MeteoraDammV2(ctx) => msg.meteora_damm_v2 = Some(ctx),
+            MarketContext::RaydiumLaunchpad(ctx) => msg.raydium_launchpad = Some(ctx),
+            MarketContext::RaydiumCpmm(ctx) => msg.raydium_cpmm = Some(ctx),
+        }
+        msg
+    }
+}
+
 /// A slippage band describing the max sellable tokens at a given slippage.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SlippageBandMsg {
     /// Slippage threshold in basis points.
     pub slippage_bps: u16,
@@ -125,6 +315,7 @@ pub struct SlippageBandMsg {
 
 /// A single take-profit level in a chained sell strategy.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct TakeProfitLevelMsg {
     /// PnL percentage threshold to trigger this level.
     pub profit_pct: f64,
@@ -137,6 +328,7 @@ pub struct TakeProfitLevelMsg {
 
 /// Client-side strategy thresholds used for automated exits.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct StrategyConfigMsg {
     /// Target take-profit percentage (legacy single-level; ignored when `take_profit_levels` is non-empty).
     pub target_profit_pct: f64,
@@ -161,9 +353,10 @@ pub struct StrategyConfigMsg {
 
 /// Auto-buy configuration for a watched wallet.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct AutoBuyConfigMsg {
     /// Which of the user's own wallets to execute the buy on.
-    pub wallet_pubkey: String,
+    pub wallet_pubkey: Pubkey58,
     /// Amount to spend in SOL lamports when the watched wallet buys in a SOL market.
     pub amount_quote_units: u64,
     /// Amount to spend in USD1 base units when the watched wallet buys in a USD1 market.
@@ -174,9 +367,10 @@ pub struct AutoBuyConfigMsg {
 
 /// A single watched wallet entry with optional auto-buy mirror config.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct WatchWalletEntryMsg {
     /// Solana pubkey of the external wallet to watch.
-    pub pubkey: String,
+    pub pubkey: Pubkey58,
     /// Optional auto-buy config. When set, the stream triggers a buy on
     /// the user's own wallet whenever the watched wallet opens a position.
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -189,6 +383,7 @@ pub struct WatchWalletEntryMsg {
 
 /// Mirror trading hardening configuration sent during Configure.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct MirrorConfigMsg {
     /// Max concurrent mirror positions per watched wallet (0 = unlimited).
     #[serde(default)]
@@ -218,6 +413,7 @@ pub struct MirrorConfigMsg {
 
 /// Server-enforced per-session and per-key limits.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct LimitsMsg {
     /// Max concurrent positions tracked at high priority.
     pub hi_capacity: u32,
@@ -239,14 +435,284 @@ pub struct LimitsMsg {
     pub max_watch_wallets_per_session: u32,
 }
 
+/// Identifies a single field of [`LimitsMsg`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LimitKind {
+    /// `hi_capacity`.
+    HiCapacity,
+    /// `max_positions_per_session`.
+    MaxPositionsPerSession,
+    /// `max_wallets_per_session`.
+    MaxWalletsPerSession,
+    /// `max_positions_per_wallet`.
+    MaxPositionsPerWallet,
+    /// `max_sessions_per_api_key`.
+    MaxSessionsPerApiKey,
+    /// `max_watch_wallets_per_session`.
+    MaxWatchWalletsPerSession,
+}
+
+impl LimitKind {
+    /// Returns the `LimitsMsg` field name for this limit.
+    pub fn field_name(self) -> &'static str {
+        match self {
+            LimitKind::HiCapacity => "hi_capacity",
+            LimitKind::MaxPositionsPerSession => "max_positions_per_session",
+            LimitKind::MaxWalletsPerSession => "max_wallets_per_session",
+            LimitKind::MaxPositionsPerWallet => "max_positions_per_wallet",
+            LimitKind::MaxSessionsPerApiKey => "max_sessions_per_api_key",
+            LimitKind::MaxWatchWalletsPerSession => "max_watch_wallets_per_session",
+        }
+    }
+}
+
+impl LimitsMsg {
+    /// Returns the value of a single limit.
+    pub fn get(&self, kind: LimitKind) -> u32 {
+        match kind {
+            LimitKind::HiCapacity => self.hi_capacity,
+            LimitKind::MaxPositionsPerSession => self.max_positions_per_session,
+            LimitKind::MaxWalletsPerSession => self.max_wallets_per_session,
+            LimitKind::MaxPositionsPerWallet => self.max_positions_per_wallet,
+            LimitKind::MaxSessionsPerApiKey => self.max_sessions_per_api_key,
+            LimitKind::MaxWatchWalletsPerSession => self.max_watch_wallets_per_session,
+        }
+    }
+}
+
+open_enum! {
+    /// Stable machine-readable code carried by `ServerMessage::Error`.
+    ///
+    /// Codes not listed here are kept verbatim in `Unknown`.
+    pub enum ErrorCode {
+        /// Code not known to this version of the crate.
+        Unknown(String),
+        /// API key missing, invalid or revoked.
+        AuthFailed => "auth_failed",
+        /// Message could not be parsed or is not valid in this state.
+        InvalidMessage => "invalid_message",
+        /// Command sent before the session was configured.
+        NotConfigured => "not_configured",
+        /// Strategy configuration was rejected.
+        InvalidStrategy => "invalid_strategy",
+        /// Referenced position is not tracked by the session.
+        UnknownPosition => "unknown_position",
+        /// `hi_capacity` exceeded.
+        HiCapacityExceeded => "hi_capacity_exceeded",
+        /// `max_positions_per_session` exceeded.
+        PositionsPerSessionExceeded => "max_positions_per_session_exceeded",
+        /// `max_wallets_per_session` exceeded.
+        WalletsPerSessionExceeded => "max_wallets_per_session_exceeded",
+        /// `max_positions_per_wallet` exceeded.
+        PositionsPerWalletExceeded => "max_positions_per_wallet_exceeded",
+        /// `max_sessions_per_api_key` exceeded.
+        SessionsPerApiKeyExceeded => "max_sessions_per_api_key_exceeded",
+        /// `max_watch_wallets_per_session` exceeded.
+        WatchWalletsPerSessionExceeded => "max_watch_wallets_per_session_exceeded",
+        /// Feature is not available on the API key's plan.
+        TierRequired => "tier_required",
+        /// Too many requests; back off before retrying.
+        RateLimited => "rate_limited",
+        /// Server could not build the requested transaction.
+        TxBuildFailed => "tx_build_failed",
+        /// Server is temporarily unavailable.
+        Unavailable => "unavailable",
+        /// Unexpected server-side failure.
+        Internal => "internal_error",
+    }
+}
+
+impl ErrorCode {
+    /// Returns true when the same request may succeed if retried after a backoff.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::SessionsPerApiKeyExceeded
+                | ErrorCode::RateLimited
+                | ErrorCode::TxBuildFailed
+                | ErrorCode::Unavailable
+                | ErrorCode::Internal
+        )
+    }
+
+    /// Returns true when the server ends the session after sending this error.
+    pub fn is_fatal_for_session(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::AuthFailed | ErrorCode::SessionsPerApiKeyExceeded
+        )
+    }
+
+    /// Returns the [`LimitsMsg`] field this error refers to, if any.
+    pub fn related_limit(&self) -> Option<LimitKind> {
+        match self {
+            ErrorCode::HiCapacityExceeded => Some(LimitKind::HiCapacity),
+            ErrorCode::PositionsPerSessionExceeded => Some(LimitKind::MaxPositionsPerSession),
+            ErrorCode::WalletsPerSessionExceeded => Some(LimitKind::MaxWalletsPerSession),
+            ErrorCode::PositionsPerWalletExceeded => Some(LimitKind::MaxPositionsPerWallet),
+            ErrorCode::SessionsPerApiKeyExceeded => Some(LimitKind::MaxSessionsPerApiKey),
+            ErrorCode::WatchWalletsPerSessionExceeded => Some(LimitKind::MaxWatchWalletsPerSession),
+            _ => None,
+        }
+    }
+}
+
+open_enum! {
+    /// Direction of a trade observed on a pool.
+    pub enum TradeSide {
+        /// Side not known to this version of the crate.
+        Other(String),
+        /// Tokens bought from the pool.
+        Buy => "buy",
+        /// Tokens sold into the pool.
+        Sell => "sell",
+    }
+}
+
+open_enum! {
+    /// Direction of pool liquidity over recent snapshots.
+    pub enum LiquidityTrend {
+        /// Trend not known to this version of the crate.
+        Other(String),
+        /// Liquidity is increasing.
+        Growing => "growing",
+        /// Liquidity is roughly unchanged.
+        Stable => "stable",
+        /// Liquidity is decreasing.
+        Draining => "draining",
+    }
+}
+
+open_enum! {
+    /// Quote asset used to pay for a buy.
+    pub enum QuoteAsset {
+        /// Asset not known to this version of the crate.
+        Other(String),
+        /// Native SOL, amounts in lamports.
+        Sol => "SOL",
+        /// USD1 stablecoin, amounts in base units.
+        Usd1 => "USD1",
+    }
+}
+
+open_enum! {
+    /// How the client submits signed transactions.
+    pub enum SendMode {
+        /// Send mode not known to this version of the crate.
+        Other(String),
+        /// Helius sender endpoint.
+        HeliusSender => "helius_sender",
+        /// Standard RPC `sendTransaction`.
+        Rpc => "rpc",
+        /// Astralane relay.
+        Astralane => "astralane",
+    }
+}
+
+open_enum! {
+    /// Why a position was exited or closed.
+    ///
+    /// Any string not listed here, including alternative spellings, is kept
+    /// verbatim in `Unknown` so it re-encodes unchanged.
+    pub enum ExitReason {
+        /// Reason not known to this version of the crate.
+        Unknown(String),
+        /// Legacy single-level take profit (`target_profit_pct`).
+        TakeProfit => "tp",
+        /// A chained take-profit level fired; see `level_index`.
+        ChainedTakeProfit => "chained_tp",
+        /// Stop loss hit.
+        StopLoss => "sl",
+        /// Trailing stop hit.
+        TrailingStop => "trailing_stop",
+        /// Stop moved to breakeven and price fell back to entry.
+        BreakevenTrail => "breakeven_trail",
+        /// Token graduated to a new DEX with `sell_on_graduation` enabled.
+        Graduation => "graduation",
+        /// Exit requested by the client via `RequestExitSignal` or `ClosePosition`.
+        Manual => "manual",
+        /// Watched wallet sold and `mirror_sell` is enabled.
+        MirrorSell => "mirror_sell",
+        /// Exit forced by the liquidity guard.
+        LiquidityGuard => "liquidity_guard",
+        /// Tokens were sold outside the stream.
+        ExternalSell => "external_sell",
+    }
+}
+
+/// What initiated an exit, derived from an [`ExitReason`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExitOrigin {
+    /// Triggered automatically by the configured strategy.
+    Strategy,
+    /// Requested explicitly by the user.
+    User,
+    /// Happened outside the stream (e.g. a sell from another app).
+    External,
+    /// Origin of an unrecognized reason.
+    Unknown,
+}
+
+impl ExitReason {
+    /// Returns what initiated this exit.
+    pub fn origin(&self) -> ExitOrigin {
+        match self {
+            ExitReason::TakeProfit
+            | ExitReason::ChainedTakeProfit
+            | ExitReason::StopLoss
+            | ExitReason::TrailingStop
+            | ExitReason::BreakevenTrail
+            | ExitReason::Graduation
+            | ExitReason::MirrorSell
+            | ExitReason::LiquidityGuard => ExitOrigin::Strategy,
+            ExitReason::Manual => ExitOrigin::User,
+            ExitReason::ExternalSell => ExitOrigin::External,
+            ExitReason::Unknown(_) => ExitOrigin::Unknown,
+        }
+    }
+
+    /// Returns true when the exit was triggered by the configured strategy.
+    pub fn is_strategy_driven(&self) -> bool {
+        self.origin() == ExitOrigin::Strategy
+    }
+
+    /// Returns true when the exit was requested explicitly by the user.
+    pub fn is_user_driven(&self) -> bool {
+        self.origin() == ExitOrigin::User
+    }
+}
+
 /// Commands sent from client to server.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ClientMessage {
+    /// Optional handshake announcing the client's protocol version and capabilities.
+    ///
+    /// Sent before `Configure`. Servers reply with `HelloOk`.
+    Hello {
+        /// Protocol version spoken by the client.
+        protocol_version: u32,
+        /// Free-form client name and version for server logs.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_name: Option<String>,
+        /// Optional protocol features the client understands.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        capabilities: Vec<Capability>,
+        /// Encodings the client can read, most preferred first. Empty means JSON only.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        encodings: Vec<Encoding>,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
+    },
     /// Keepalive ping from client.
     Ping {
         /// Client timestamp in Unix milliseconds.
         client_time_ms: u64,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Initial session configuration for wallets and strategy.
     Configure {
@@ -255,12 +721,13 @@ pub enum ClientMessage {
             alias = "wallet_pubkey",
             deserialize_with = "deserialize_wallet_pubkeys"
         )]
-        wallet_pubkeys: Vec<String>,
+        #[cfg_attr(feature = "schema", schemars(schema_with = "schema::wallet_pubkeys"))]
+        wallet_pubkeys: Vec<Pubkey58>,
         /// Strategy thresholds for the session.
         strategy: StrategyConfigMsg,
-        /// How the client will submit the signed transaction (e.g. "helius_sender", "rpc", "astralane").
+        /// How the client will submit the signed transaction.
         #[serde(default)]
-        send_mode: Option<String>,
+        send_mode: Option<SendMode>,
         /// Priority fee tip in lamports (required for some send modes).
         #[serde(default)]
         tip_lamports: Option<u64>,
@@ -270,11 +737,17 @@ pub enum ClientMessage {
         /// Mirror trading hardening configuration.
         #[serde(default, skip_serializing_if = "Option::is_none")]
         mirror_config: Option<MirrorConfigMsg>,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Update strategy thresholds for an active session.
     UpdateStrategy {
         /// New strategy configuration.
         strategy: StrategyConfigMsg,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Request that a tracked position be closed.
     ClosePosition {
@@ -283,7 +756,10 @@ pub enum ClientMessage {
         position_id: Option<u64>,
         /// Optional token account key for lookup when ID is unknown.
         #[serde(skip_serializing_if = "Option::is_none")]
-        token_account: Option<String>,
+        token_account: Option<Pubkey58>,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Request an immediate exit signal and unsigned transaction.
     ///
@@ -295,21 +771,31 @@ pub enum ClientMessage {
         position_id: Option<u64>,
         /// Optional token account key for lookup when ID is unknown.
         #[serde(skip_serializing_if = "Option::is_none")]
-        token_account: Option<String>,
+        token_account: Option<Pubkey58>,
         /// Optional slippage tolerance, in basis points.
         #[serde(skip_serializing_if = "Option::is_none")]
         slippage_bps: Option<u16>,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Replace the set of monitored wallets for an active session.
     UpdateWallets {
         /// Full replacement list of wallet pubkeys.
         #[serde(deserialize_with = "deserialize_wallet_pubkeys")]
-        wallet_pubkeys: Vec<String>,
+        #[cfg_attr(feature = "schema", schemars(schema_with = "schema::wallet_pubkeys"))]
+        wallet_pubkeys: Vec<Pubkey58>,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Replace the set of watched external wallets for copy trading.
     UpdateWatchWallets {
         /// Full replacement list of watch wallet entries.
         watch_wallets: Vec<WatchWalletEntryMsg>,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Override the strategy for a single active position.
     ///
@@ -320,6 +806,21 @@ pub enum ClientMessage {
         position_id: u64,
         /// Full strategy replacement for this position.
         strategy: StrategyConfigMsg,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
+    },
+    /// Resume a sequenced session after a reconnect.
+    ///
+    /// The server replays events after `last_seq` if they are still buffered.
+    Resume {
+        /// Session to resume.
+        session_id: u64,
+        /// Highest sequence number the client has processed.
+        last_seq: u64,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Report the outcome of a mirror buy transaction back to the stream.
     ///
@@ -329,15 +830,19 @@ pub enum ClientMessage {
     /// periodic cleanup sweep.
     MirrorBuyResult {
         /// Token mint pubkey that the mirror buy targeted.
-        mint: String,
+        mint: Pubkey58,
         /// True if the transaction was successfully submitted to the network.
         /// False if signing or submission failed.
         success: bool,
+        /// Optional client-chosen identifier echoed in the matching `Ack` or `Error`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
 }
 
 /// Events and responses sent from server to client.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ServerMessage {
     /// Successful handshake response with limits.
@@ -348,18 +853,35 @@ pub enum ServerMessage {
         server_time_ms: u64,
         /// Effective limits for the session/API key.
         limits: LimitsMsg,
+        /// Protocol version spoken for this session. Absent from legacy servers.
+        #[serde(default = "handshake::legacy_protocol_version")]
+        protocol_version: u32,
+        /// Optional protocol features enabled for this session.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        capabilities: Vec<Capability>,
+        /// Encoding of the server's frames after this one. Absent means JSON.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encoding: Option<Encoding>,
     },
     /// Keepalive pong from server.
     Pong {
         /// Server timestamp in Unix milliseconds.
         server_time_ms: u64,
     },
+    /// Acknowledges a client command that carried a `request_id`.
+    Ack {
+        /// The `request_id` of the acknowledged command.
+        request_id: u64,
+    },
     /// Error response for invalid requests or runtime failures.
     Error {
         /// Stable machine-readable error code.
-        code: String,
+        code: ErrorCode,
         /// Human-readable error message.
         message: String,
+        /// `request_id` of the command that caused the error, if any.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        request_id: Option<u64>,
     },
     /// Incremental PnL update for a position.
     PnlUpdate {
@@ -381,14 +903,40 @@ pub enum ServerMessage {
         #[serde(default, skip_serializing_if = "std::ops::Not::not")]
         watched: bool,
     },
+    /// Compact PnL update carrying only the fields that changed.
+    ///
+    /// Only sent to clients that negotiated `Capability::PnlDelta`. The delta
+    /// applies to the previous `PnlUpdate` (or delta) for the same position,
+    /// identified by its `server_time_ms`. Use [`PnlDeltaDecoder`] to
+    /// reconstruct full `PnlUpdate` messages.
+    PnlDelta {
+        /// Internal position identifier.
+        position_id: u64,
+        /// `server_time_ms` of the update this delta applies to.
+        base_server_time_ms: u64,
+        /// Server timestamp in Unix milliseconds.
+        server_time_ms: u64,
+        /// New profit/loss in quote units, if changed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profit_units: Option<i64>,
+        /// New estimated proceeds in quote units, if changed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        proceeds_units: Option<u64>,
+        /// New token price in quote units, if changed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_price_quote: Option<u64>,
+        /// New market cap in quote units, if changed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        market_cap_quote: Option<u64>,
+    },
     /// Liquidity snapshot for a position with slippage bands.
     LiquiditySnapshot {
         /// Internal position identifier.
         position_id: u64,
         /// Slippage bands describing sellable amounts at each threshold.
         bands: Vec<SlippageBandMsg>,
-        /// Liquidity trend: "growing", "stable", or "draining".
-        liquidity_trend: String,
+        /// Liquidity trend over recent snapshots.
+        liquidity_trend: LiquidityTrend,
         /// Server timestamp in Unix milliseconds.
         server_time_ms: u64,
         /// True when this position belongs to a watched (copy-traded) wallet.
@@ -401,8 +949,8 @@ pub enum ServerMessage {
         position_id: u64,
         /// Unix timestamp in milliseconds when the trade was observed.
         time_ms: u64,
-        /// Trade direction: "buy" or "sell".
-        side: String,
+        /// Trade direction.
+        side: TradeSide,
         /// Token amount traded in native units.
         token_amount: u64,
         /// Quote (SOL) amount traded in lamports.
@@ -411,10 +959,10 @@ pub enum ServerMessage {
         price_quote: u64,
         /// Wallet that initiated the swap.
         #[serde(skip_serializing_if = "Option::is_none")]
-        maker: Option<String>,
+        maker: Option<Pubkey58>,
         /// Transaction signature (base58-encoded).
         #[serde(skip_serializing_if = "Option::is_none")]
-        tx_signature: Option<String>,
+        tx_signature: Option<Signature58>,
         /// True when this position belongs to a watched (copy-traded) wallet.
         #[serde(default, skip_serializing_if = "std::ops::Not::not")]
         watched: bool,
@@ -422,15 +970,15 @@ pub enum ServerMessage {
     /// Balance update for a tracked wallet/mint.
     BalanceUpdate {
         /// Wallet pubkey the balance belongs to.
-        wallet_pubkey: String,
+        wallet_pubkey: Pubkey58,
         /// Token mint pubkey.
-        mint: String,
+        mint: Pubkey58,
         /// Optional token account pubkey.
         #[serde(skip_serializing_if = "Option::is_none")]
-        token_account: Option<String>,
+        token_account: Option<Pubkey58>,
         /// Optional token program pubkey.
         #[serde(skip_serializing_if = "Option::is_none")]
-        token_program: Option<String>,
+        token_program: Option<Pubkey58>,
         /// Token amount in native units.
         tokens: u64,
         /// Slot the balance snapshot came from.
@@ -441,14 +989,14 @@ pub enum ServerMessage {
         /// Internal position identifier.
         position_id: u64,
         /// Wallet pubkey associated with the position.
-        wallet_pubkey: String,
+        wallet_pubkey: Pubkey58,
         /// Token mint pubkey.
-        mint: String,
+        mint: Pubkey58,
         /// Token account pubkey.
-        token_account: String,
+        token_account: Pubkey58,
         /// Optional token program pubkey.
         #[serde(skip_serializing_if = "Option::is_none")]
-        token_program: Option<String>,
+        token_program: Option<Pubkey58>,
         /// Position token amount in native units.
         tokens: u64,
         /// Entry cost in quote units.
@@ -491,14 +1039,14 @@ pub enum ServerMessage {
         /// Internal position identifier.
         position_id: u64,
         /// Wallet pubkey associated with the position.
-        wallet_pubkey: String,
+        wallet_pubkey: Pubkey58,
         /// Token mint pubkey.
-        mint: String,
+        mint: Pubkey58,
         /// Optional token account pubkey.
         #[serde(skip_serializing_if = "Option::is_none")]
-        token_account: Option<String>,
-        /// Reason string for the close event.
-        reason: String,
+        token_account: Option<Pubkey58>,
+        /// Reason for the close event.
+        reason: ExitReason,
         /// Identifier of the mirror source that triggered this position (e.g. watched wallet pubkey).
         #[serde(default, skip_serializing_if = "Option::is_none")]
         mirror_source: Option<String>,
@@ -515,21 +1063,21 @@ pub enum ServerMessage {
         /// Internal position identifier.
         position_id: u64,
         /// Wallet pubkey associated with the position.
-        wallet_pubkey: String,
+        wallet_pubkey: Pubkey58,
         /// Token mint pubkey.
-        mint: String,
+        mint: Pubkey58,
         /// Optional token account pubkey.
         #[serde(skip_serializing_if = "Option::is_none")]
-        token_account: Option<String>,
+        token_account: Option<Pubkey58>,
         /// Optional token program pubkey.
         #[serde(skip_serializing_if = "Option::is_none")]
-        token_program: Option<String>,
+        token_program: Option<Pubkey58>,
         /// Position token amount in native units.
         position_tokens: u64,
         /// Profit/loss in quote units.
         profit_units: i64,
         /// Trigger reason for the exit.
-        reason: String,
+        reason: ExitReason,
         /// Trigger timestamp in Unix milliseconds.
         triggered_at_ms: u64,
         /// Optional market metadata for this position.
@@ -559,22 +1107,22 @@ pub enum ServerMessage {
         /// Session identifier for correlation.
         session_id: u64,
         /// Watched wallet that triggered this mirror buy.
-        watched_wallet: String,
+        watched_wallet: Pubkey58,
         /// Token mint pubkey being bought.
-        mint: String,
+        mint: Pubkey58,
         /// User's own wallet that will execute the buy.
-        user_wallet: String,
+        user_wallet: Pubkey58,
         /// Amount to spend in quote units (lamports for SOL, base units for USD1).
         amount_quote_units: u64,
-        /// Quote asset: "SOL" or "USD1".
-        input: String,
+        /// Quote asset used for the buy.
+        input: QuoteAsset,
         /// Base64-encoded unsigned buy transaction.
         unsigned_tx_b64: String,
         /// Slippage tolerance in basis points.
         slippage_bps: u16,
-        /// Transaction send mode (e.g. "helius_sender", "rpc", "astralane").
+        /// Transaction send mode.
         #[serde(skip_serializing_if = "Option::is_none")]
-        send_mode: Option<String>,
+        send_mode: Option<SendMode>,
         /// Priority fee tip in lamports.
         #[serde(skip_serializing_if = "Option::is_none")]
         tip_lamports: Option<u64>,
@@ -585,21 +1133,30 @@ pub enum ServerMessage {
     /// Notification that a mirror buy could not be executed.
     MirrorBuyFailed {
         /// Watched wallet that triggered the failed buy.
-        watched_wallet: String,
+        watched_wallet: Pubkey58,
         /// Token mint pubkey that was targeted.
-        mint: String,
+        mint: Pubkey58,
         /// Human-readable reason for the failure.
         reason: String,
     },
     /// Notification that a watched wallet has been auto-disabled.
     MirrorWalletAutoDisabled {
         /// Watched wallet pubkey that was disabled.
-        watched_wallet: String,
+        watched_wallet: Pubkey58,
         /// Reason for auto-disable (e.g. "consecutive_losses").
         reason: String,
         /// Number of consecutive losses that triggered the disable.
         loss_count: u32,
     },
+    /// Several messages delivered in one frame.
+    ///
+    /// Only sent to clients that negotiated `Capability::Batch`. Use
+    /// [`ServerMessage::into_messages`] or [`flatten_batches`] to process a
+    /// batch as if its messages had arrived one by one.
+    Batch {
+        /// Batched messages, in the order they would have been sent.
+        messages: Vec<ServerMessage>,
+    },
 }
 
 impl ClientMessage {
@@ -607,18 +1164,53 @@ impl ClientMessage {
     pub fn from_text(text: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(text)
     }
+
+    /// Returns the correlation identifier carried by this command.
+    pub fn request_id(&self) -> Option<u64> {
+        match self {
+            ClientMessage::Hello { request_id, .. }
+            | ClientMessage::Ping { request_id, .. }
+            | ClientMessage::Configure { request_id, .. }
+            | ClientMessage::UpdateStrategy { request_id, .. }
+            | ClientMessage::ClosePosition { request_id, .. }
+            | ClientMessage::RequestExitSignal { request_id, .. }
+            | ClientMessage::UpdateWallets { request_id, .. }
+            | ClientMessage::UpdateWatchWallets { request_id, .. }
+            | ClientMessage::UpdatePositionStrategy { request_id, .. }
+            | ClientMessage::Resume { request_id, .. }
+            | ClientMessage::MirrorBuyResult { request_id, .. } => *request_id,
+        }
+    }
+
+    /// Sets the correlation identifier, returning the updated command.
+    pub fn with_request_id(mut self, id: u64) -> Self {
+        match &mut self {
+            ClientMessage::Hello { request_id, .. }
+            | ClientMessage::Ping { request_id, .. }
+            | ClientMessage::Configure { request_id, .. }
+            | ClientMessage::UpdateStrategy { request_id, .. }
+            | ClientMessage::ClosePosition { request_id, .. }
+            | ClientMessage::RequestExitSignal { request_id, .. }
+            | ClientMessage::UpdateWallets { request_id, .. }
+            | ClientMessage::UpdateWatchWallets { request_id, .. }
+            | ClientMessage::UpdatePositionStrategy { request_id, .. }
+            | ClientMessage::Resume { request_id, .. }
+            | ClientMessage::MirrorBuyResult { request_id, .. } => *request_id = Some(id),
+        }
+        self
+    }
 }
 
 /// Deserializes either a single wallet pubkey string or an array.
-fn deserialize_wallet_pubkeys<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+fn deserialize_wallet_pubkeys<'de, D>(deserializer: D) -> Result<Vec<Pubkey58>, D::Error>
 where
     D: Deserializer<'de>,
 {
     #[derive(Deserialize)]
     #[serde(untagged)]
     enum WalletPubkeysField {
-        One(String),
-        Many(Vec<String>),
+        One(Pubkey58),
+        Many(Vec<Pubkey58>),
     }
 
     match WalletPubkeysField::deserialize(deserializer)? {
@@ -632,6 +1224,15 @@ impl ServerMessage {
     pub fn to_text(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(self)
     }
+
+    /// Returns the client `request_id` this message responds to, if any.
+    pub fn request_id(&self) -> Option<u64> {
+        match self {
+            ServerMessage::Ack { request_id } => Some(*request_id),
+            ServerMessage::Error { request_id, .. } => *request_id,
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -647,6 +1248,14 @@ mod tests {
         assert_eq!(value, decoded);
     }
 
+    fn key(n: u8) -> Pubkey58 {
+        Pubkey58::from_bytes(&[n; 32])
+    }
+
+    fn signature(n: u8) -> Signature58 {
+        Signature58::from_bytes(&[n; 64])
+    }
+
     fn base_context() -> MarketContextMsg {
         MarketContextMsg {
             market_type: MarketTypeMsg::PumpFun,
@@ -672,8 +1281,8 @@ mod tests {
         let mut ctx = base_context();
         ctx.market_type = MarketTypeMsg::PumpSwap;
         ctx.pumpswap = Some(PumpSwapContextMsg {
-            pool: "11111111111111111111111111111111".to_string(),
-            global_config: Some("22222222222222222222222222222222".to_string()),
+            pool: key(1),
+            global_config: Some(key(2)),
         });
         round_trip(ctx);
     }
@@ -683,9 +1292,9 @@ mod tests {
         let mut ctx = base_context();
         ctx.market_type = MarketTypeMsg::MeteoraDbc;
         ctx.meteora_dbc = Some(MeteoraDbcContextMsg {
-            pool: "11111111111111111111111111111111".to_string(),
-            config: "22222222222222222222222222222222".to_string(),
-            quote_mint: "33333333333333333333333333333333".to_string(),
+            pool: key(1),
+            config: key(2),
+            quote_mint: key(3),
         });
         round_trip(ctx);
     }
@@ -694,9 +1303,7 @@ mod tests {
     fn market_context_round_trip_meteora_damm_v2() {
         let mut ctx = base_context();
         ctx.market_type = MarketTypeMsg::MeteoraDammV2;
-        ctx.meteora_damm_v2 = Some(MeteoraDammV2ContextMsg {
-            pool: "11111111111111111111111111111111".to_string(),
-        });
+        ctx.meteora_damm_v2 = Some(MeteoraDammV2ContextMsg { pool: key(1) });
         round_trip(ctx);
     }
 
@@ -705,11 +1312,11 @@ mod tests {
         let mut ctx = base_context();
         ctx.market_type = MarketTypeMsg::RaydiumLaunchpad;
         ctx.raydium_launchpad = Some(RaydiumLaunchpadContextMsg {
-            pool: "11111111111111111111111111111111".to_string(),
-            config: "22222222222222222222222222222222".to_string(),
-            platform: "33333333333333333333333333333333".to_string(),
-            quote_mint: "44444444444444444444444444444444".to_string(),
-            user_quote_account: "55555555555555555555555555555555".to_string(),
+            pool: key(1),
+            config: key(2),
+            platform: key(3),
+            quote_mint: key(4),
+            user_quote_account: key(5),
         });
         round_trip(ctx);
     }
@@ -719,21 +1326,68 @@ mod tests {
         let mut ctx = base_context();
         ctx.market_type = MarketTypeMsg::RaydiumCpmm;
         ctx.raydium_cpmm = Some(RaydiumCpmmContextMsg {
-            pool: "11111111111111111111111111111111".to_string(),
-            config: "22222222222222222222222222222222".to_string(),
-            quote_mint: "33333333333333333333333333333333".to_string(),
-            user_quote_account: "44444444444444444444444444444444".to_string(),
+            pool: key(1),
+            config: key(2),
+            quote_mint: key(3),
+            user_quote_account: key(4),
         });
         round_trip(ctx);
     }
 
+    #[test]
+    fn market_context_typed_view_round_trip() {
+        let mut ctx = base_context();
+        ctx.market_type = MarketTypeMsg::MeteoraDammV2;
+        ctx.meteora_damm_v2 = Some(MeteoraDammV2ContextMsg { pool: key(1) });
+
+        let typed = ctx.context().expect("typed context");
+        assert_eq!(typed.market_type(), MarketTypeMsg::MeteoraDammV2);
+        assert_eq!(MarketContextMsg::from(typed.clone()), ctx);
+        assert_eq!(
+            serde_json::to_string(&typed).expect("serialize"),
+            serde_json::to_string(&ctx).expect("serialize")
+        );
+        round_trip(typed);
+    }
+
+    #[test]
+    fn market_context_typed_view_rejects_missing_context() {
+        let ctx = base_context();
+        assert_eq!(
+            MarketContext::try_from(ctx),
+            Err(MarketContextError::Missing {
+                market_type: MarketTypeMsg::PumpFun,
+            })
+        );
+    }
+
+    #[test]
+    fn market_context_typed_view_rejects_mismatched_and_multiple_contexts() {
+        let mut ctx = base_context();
+        ctx.market_type = MarketTypeMsg::PumpSwap;
+        ctx.pumpfun = Some(PumpFunContextMsg {});
+        assert_eq!(
+            ctx.context(),
+            Err(MarketContextError::Unexpected {
+                market_type: MarketTypeMsg::PumpSwap,
+                found: MarketTypeMsg::PumpFun,
+            })
+        );
+
+        ctx.pumpswap = Some(PumpSwapContextMsg {
+            pool: key(1),
+            global_config: None,
+        });
+        assert!(ctx.context().is_err());
+
+        let raw = r#"{"market_type":"pump_swap","pumpfun":{}}"#;
+        assert!(serde_json::from_str::<MarketContext>(raw).is_err());
+    }
+
     #[test]
     fn client_configure_round_trip() {
         let msg = ClientMessage::Configure {
-            wallet_pubkeys: vec![
-                "11111111111111111111111111111111".to_string(),
-                "22222222222222222222222222222222".to_string(),
-            ],
+            wallet_pubkeys: vec![key(1), key(2)],
             strategy: StrategyConfigMsg {
                 target_profit_pct: 5.0,
                 stop_loss_pct: 1.5,
@@ -747,6 +1401,7 @@ mod tests {
             tip_lamports: None,
             watch_wallets: vec![],
             mirror_config: None,
+            request_id: None,
         };
 
         round_trip(msg);
@@ -767,7 +1422,7 @@ mod tests {
         assert_eq!(
             msg,
             ClientMessage::Configure {
-                wallet_pubkeys: vec!["11111111111111111111111111111111".to_string()],
+                wallet_pubkeys: vec![key(0)],
                 strategy: StrategyConfigMsg {
                     target_profit_pct: 5.0,
                     stop_loss_pct: 1.5,
@@ -781,6 +1436,7 @@ mod tests {
                 tip_lamports: None,
                 watch_wallets: vec![],
                 mirror_config: None,
+                request_id: None,
             }
         );
 
@@ -807,6 +1463,7 @@ mod tests {
                 position_id: Some(123),
                 token_account: None,
                 slippage_bps: Some(42),
+                request_id: None,
             }
         );
 
@@ -833,6 +1490,9 @@ mod tests {
                 max_sessions_per_api_key: 1,
                 max_watch_wallets_per_session: 10,
             },
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: vec![Capability::TakeProfitLevels, Capability::TradeTicks],
+            encoding: None,
         };
 
         round_trip(msg);
@@ -848,22 +1508,22 @@ mod tests {
             meteora_damm_v2: None,
             raydium_launchpad: None,
             raydium_cpmm: Some(RaydiumCpmmContextMsg {
-                pool: "11111111111111111111111111111111".to_string(),
-                config: "22222222222222222222222222222222".to_string(),
-                quote_mint: "33333333333333333333333333333333".to_string(),
-                user_quote_account: "44444444444444444444444444444444".to_string(),
+                pool: key(1),
+                config: key(2),
+                quote_mint: key(3),
+                user_quote_account: key(4),
             }),
         };
         let msg = ServerMessage::ExitSignalWithTx {
             session_id: 7,
             position_id: 8,
-            wallet_pubkey: "55555555555555555555555555555555".to_string(),
-            mint: "11111111111111111111111111111111".to_string(),
-            token_account: Some("22222222222222222222222222222222".to_string()),
+            wallet_pubkey: key(5),
+            mint: key(1),
+            token_account: Some(key(2)),
             token_program: None,
             position_tokens: 10,
             profit_units: 5,
-            reason: "tp".to_string(),
+            reason: ExitReason::TakeProfit,
             triggered_at_ms: 123,
             market_context: Some(ctx),
             unsigned_tx_b64: "dGVzdA==".to_string(),
@@ -878,7 +1538,7 @@ mod tests {
     #[test]
     fn strategy_with_take_profit_levels_round_trip() {
         let msg = ClientMessage::Configure {
-            wallet_pubkeys: vec!["11111111111111111111111111111111".to_string()],
+            wallet_pubkeys: vec![key(1)],
             strategy: StrategyConfigMsg {
                 target_profit_pct: 0.0,
                 stop_loss_pct: 5.0,
@@ -903,6 +1563,7 @@ mod tests {
             tip_lamports: None,
             watch_wallets: vec![],
             mirror_config: None,
+            request_id: None,
         };
         round_trip(msg);
     }
@@ -931,13 +1592,13 @@ mod tests {
         let msg = ServerMessage::ExitSignalWithTx {
             session_id: 1,
             position_id: 2,
-            wallet_pubkey: "11111111111111111111111111111111".to_string(),
-            mint: "22222222222222222222222222222222".to_string(),
+            wallet_pubkey: key(1),
+            mint: key(2),
             token_account: None,
             token_program: None,
             position_tokens: 1000,
             profit_units: 50,
-            reason: "chained_tp".to_string(),
+            reason: ExitReason::ChainedTakeProfit,
             triggered_at_ms: 999,
             market_context: None,
             unsigned_tx_b64: "dGVzdA==".to_string(),
@@ -968,12 +1629,12 @@ mod tests {
         let msg = ServerMessage::TradeTick {
             position_id: 7,
             time_ms: 1700000000123,
-            side: "buy".to_string(),
+            side: TradeSide::Buy,
             token_amount: 50_000_000,
             quote_amount: 1_200_000_000,
             price_quote: 24_000,
-            maker: Some("11111111111111111111111111111111".to_string()),
-            tx_signature: Some("22222222222222222222222222222222".to_string()),
+            maker: Some(key(1)),
+            tx_signature: Some(signature(2)),
             watched: false,
         };
         round_trip(msg);
@@ -995,10 +1656,208 @@ mod tests {
                     coverage_pct: 100.0,
                 },
             ],
-            liquidity_trend: "growing".to_string(),
+            liquidity_trend: LiquidityTrend::Growing,
             server_time_ms: 999,
             watched: false,
         };
         round_trip(msg);
     }
+
+    #[test]
+    fn open_enums_map_known_values() {
+        assert_eq!(TradeSide::from("sell"), TradeSide::Sell);
+        assert_eq!(QuoteAsset::from("USD1"), QuoteAsset::Usd1);
+        assert_eq!(
+            serde_json::to_string(&SendMode::HeliusSender).expect("serialize"),
+            "\"helius_sender\""
+        );
+        assert_eq!(
+            serde_json::from_str::<LiquidityTrend>("\"draining\"").expect("deserialize"),
+            LiquidityTrend::Draining
+        );
+    }
+
+    #[test]
+    fn open_enums_round_trip_unknown_values() {
+        let raw = r#"{
+            "type":"trade_tick",
+            "position_id":1,
+            "time_ms":2,
+            "side":"swap",
+            "token_amount":3,
+            "quote_amount":4,
+            "price_quote":5
+        }"#;
+        let msg: ServerMessage = serde_json::from_str(raw).expect("deserialize");
+        let ServerMessage::TradeTick { side, .. } = &msg else {
+            panic!("expected TradeTick");
+        };
+        assert_eq!(side, &TradeSide::Other("swap".to_string()));
+        assert_eq!(side.as_str(), "swap");
+
+        let encoded = serde_json::to_value(&msg).expect("serialize");
+        assert_eq!(encoded.get("side"), Some(&serde_json::json!("swap")));
+        round_trip(msg);
+        round_trip(SendMode::Other("jito".to_string()));
+    }
+
+    #[test]
+    fn exit_reason_parses_wire_values_and_origin() {
+        assert_eq!(ExitReason::from("tp"), ExitReason::TakeProfit);
+        assert_eq!(ExitReason::from("sl"), ExitReason::StopLoss);
+        assert_eq!(ExitReason::StopLoss.as_str(), "sl");
+        assert!(ExitReason::ChainedTakeProfit.is_strategy_driven());
+        assert!(ExitReason::Manual.is_user_driven());
+        assert_eq!(ExitReason::ExternalSell.origin(), ExitOrigin::External);
+
+        let unknown = ExitReason::from("rug_detected");
+        assert_eq!(unknown, ExitReason::Unknown("rug_detected".to_string()));
+        assert_eq!(unknown.origin(), ExitOrigin::Unknown);
+        round_trip(unknown);
+
+        // Other spellings are not rewritten to the canonical one.
+        let stop_loss: ExitReason = serde_json::from_str(r#""stop_loss""#).expect("deserialize");
+        assert_eq!(
+            serde_json::to_string(&stop_loss).expect("serialize"),
+            r#""stop_loss""#
+        );
+    }
+
+    #[test]
+    fn position_closed_reason_is_typed() {
+        let raw = r#"{
+            "type":"position_closed",
+            "position_id":1,
+            "wallet_pubkey":"11111111111111111111111111111111",
+            "mint":"11111111111111111111111111111111",
+            "reason":"trailing_stop",
+            "slot":9
+        }"#;
+        let msg: ServerMessage = serde_json::from_str(raw).expect("deserialize");
+        let ServerMessage::PositionClosed { reason, .. } = &msg else {
+            panic!("expected PositionClosed");
+        };
+        assert_eq!(reason, &ExitReason::TrailingStop);
+        round_trip(msg);
+    }
+
+    #[test]
+    fn error_code_semantics() {
+        let limits = LimitsMsg {
+            hi_capacity: 256,
+            pnl_flush_ms: 100,
+            max_positions_per_session: 256,
+            max_wallets_per_session: 8,
+            max_positions_per_wallet: 64,
+            max_sessions_per_api_key: 1,
+            max_watch_wallets_per_session: 10,
+        };
+        let code = ErrorCode::WatchWalletsPerSessionExceeded;
+        let kind = code.related_limit().expect("limit");
+        assert_eq!(kind.field_name(), "max_watch_wallets_per_session");
+        assert_eq!(limits.get(kind), 10);
+        assert!(!code.is_retryable());
+
+        assert!(ErrorCode::RateLimited.is_retryable());
+        assert!(ErrorCode::AuthFailed.is_fatal_for_session());
+        assert!(!ErrorCode::Unknown("teapot".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn error_code_spellings_survive_round_trip() {
+        for raw in ["auth_failed", "unauthorized", "internal_error", "internal"] {
+            let json = format!(r#"{{"type":"error","code":"{raw}","message":""}}"#);
+            let msg: ServerMessage = serde_json::from_str(&json).expect("deserialize");
+            let encoded = serde_json::to_value(&msg).expect("serialize");
+            assert_eq!(encoded.get("code"), Some(&serde_json::json!(raw)));
+        }
+        assert_eq!(
+            ErrorCode::from("unauthorized"),
+            ErrorCode::Unknown("unauthorized".to_string())
+        );
+    }
+
+    #[test]
+    fn server_error_round_trip() {
+        let raw = r#"{"type":"error","code":"rate_limited","message":"slow down"}"#;
+        let msg: ServerMessage = serde_json::from_str(raw).expect("deserialize");
+        assert_eq!(
+            msg,
+            ServerMessage::Error {
+                code: ErrorCode::RateLimited,
+                message: "slow down".to_string(),
+                request_id: None,
+            }
+        );
+        assert_eq!(msg.to_text().expect("serialize"), raw);
+        round_trip(ServerMessage::Error {
+            code: ErrorCode::Unknown("new_code".to_string()),
+            message: "future".to_string(),
+            request_id: Some(3),
+        });
+    }
+
+    #[test]
+    fn request_id_is_optional_and_echoed() {
+        let raw = r#"{"type":"close_position","position_id":5}"#;
+        let msg = ClientMessage::from_text(raw).expect("deserialize");
+        assert_eq!(msg.request_id(), None);
+
+        let msg = msg.with_request_id(77);
+        assert_eq!(msg.request_id(), Some(77));
+        let encoded = serde_json::to_value(&msg).expect("serialize");
+        assert_eq!(encoded.get("request_id"), Some(&serde_json::json!(77)));
+        round_trip(msg);
+
+        let ack = ServerMessage::Ack { request_id: 77 };
+        assert_eq!(ack.request_id(), Some(77));
+        assert_eq!(
+            ack.to_text().expect("serialize"),
+            r#"{"type":"ack","request_id":77}"#
+        );
+        round_trip(ack);
+    }
+
+    #[test]
+    fn hello_round_trip() {
+        let msg = ClientMessage::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_name: Some("bot/0.1".to_string()),
+            capabilities: Capability::SUPPORTED.to_vec(),
+            encodings: Encoding::supported(),
+            request_id: None,
+        };
+        round_trip(msg);
+    }
+
+    #[test]
+    fn every_fixture_round_trips_json() {
+        for msg in fixtures::client_messages() {
+            round_trip(msg);
+        }
+        for msg in fixtures::server_messages() {
+            round_trip(msg);
+        }
+    }
+
+    #[test]
+    fn legacy_hello_ok_defaults_protocol_version() {
+        let raw = r#"{
+            "type":"hello_ok",
+            "session_id":1,
+            "server_time_ms":2,
+            "limits":{"hi_capacity":1,"pnl_flush_ms":100,"max_positions_per_session":1}
+        }"#;
+        let msg: ServerMessage = serde_json::from_str(raw).expect("deserialize");
+        let ServerMessage::HelloOk {
+            protocol_version,
+            capabilities,
+            ..
+        } = msg
+        else {
+            panic!("expected HelloOk");
+        };
+        assert_eq!(protocol_version, MIN_PROTOCOL_VERSION);
+        assert!(capabilities.is_empty());
+    }
 }
@@ -0,0 +1,456 @@
+//! Session-level validation of [`ClientMessage`]s against [`LimitsMsg`].
+//!
+//! [`SessionPolicy`] mirrors the checks the stream server runs before acting
+//! on a command and produces the same `ServerMessage::Error`. Test servers can
+//! use it to enforce limits, and clients to pre-flight commands.
+//!
+//! A limit of 0 is treated as "not enforced", matching `LimitsMsg` from
+//! servers that predate the field.
+
+use std::fmt;
+
+use crate::{
+    ClientMessage, ErrorCode, LimitKind, LimitsMsg, Pubkey58, ServerMessage, WatchWalletEntryMsg,
+};
+
+/// A command rejected by [`SessionPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    /// Error code the server sends.
+    pub code: ErrorCode,
+    /// Human-readable error message.
+    pub message: String,
+    /// `request_id` of the rejected command.
+    pub request_id: Option<u64>,
+}
+
+impl Rejection {
+    /// Returns the `ServerMessage::Error` the server sends for this rejection.
+    pub fn into_message(self) -> ServerMessage {
+        ServerMessage::Error {
+            code: self.code,
+            message: self.message,
+            request_id: self.request_id,
+        }
+    }
+}
+
+impl From<Rejection> for ServerMessage {
+    fn from(rejection: Rejection) -> Self {
+        rejection.into_message()
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/// Tracks one session's configuration and checks commands against its limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionPolicy {
+    limits: LimitsMsg,
+    wallets: Option<Vec<Pubkey58>>,
+    /// `(watched wallet, auto_buy wallet)` of each auto-buying watch entry.
+    auto_buy_wallets: Vec<(Pubkey58, Pubkey58)>,
+}
+
+impl SessionPolicy {
+    /// Creates a policy for an unconfigured session.
+    pub fn new(limits: LimitsMsg) -> Self {
+        Self {
+            limits,
+            wallets: None,
+            auto_buy_wallets: Vec::new(),
+        }
+    }
+
+    /// Limits being enforced.
+    pub fn limits(&self) -> &LimitsMsg {
+        &self.limits
+    }
+
+    /// True once a `Configure` has been accepted.
+    pub fn is_configured(&self) -> bool {
+        self.wallets.is_some()
+    }
+
+    /// Wallets of the session, once configured.
+    pub fn wallets(&self) -> &[Pubkey58] {
+        self.wallets.as_deref().unwrap_or_default()
+    }
+
+    /// Checks a command without changing the session.
+    pub fn check(&self, msg: &ClientMessage) -> Result<(), Rejection> {
+        let reject = |code: ErrorCode, message: String| {
+            Err(Rejection {
+                code,
+                message,
+                request_id: msg.request_id(),
+            })
+        };
+
+        let needs_configure = !matches!(
+            msg,
+            ClientMessage::Hello { .. }
+                | ClientMessage::Ping { .. }
+                | ClientMessage::Configure { .. }
+                | ClientMessage::Resume { .. }
+        );
+        if needs_configure && !self.is_configured() {
+            return reject(
+                ErrorCode::NotConfigured,
+                "session is not configured; send configure first".to_string(),
+            );
+        }
+        if let Some(ServerMessage::Error { code, message, .. }) = msg.strategy_error() {
+            return reject(code, message);
+        }
+
+        let (code, message) = match msg {
+            ClientMessage::Configure {
+                wallet_pubkeys,
+                watch_wallets,
+                ..
+            } => match self
+                .check_wallets(wallet_pubkeys)
+                .or_else(|| self.check_watch_wallets(watch_wallets, wallet_pubkeys))
+            {
+                Some(error) => error,
+                None => return Ok(()),
+            },
+            ClientMessage::UpdateWallets { wallet_pubkeys, .. } => {
+                match self.check_wallets(wallet_pubkeys).or_else(|| {
+                    check_auto_buy_wallets(
+                        self.auto_buy_wallets
+                            .iter()
+                            .map(|(watched, wallet)| (watched, wallet)),
+                        wallet_pubkeys,
+                    )
+                }) {
+                    Some(error) => error,
+                    None => return Ok(()),
+                }
+            }
+            ClientMessage::UpdateWatchWallets { watch_wallets, .. } => {
+                match self.check_watch_wallets(watch_wallets, self.wallets()) {
+                    Some(error) => error,
+                    None => return Ok(()),
+                }
+            }
+            ClientMessage::ClosePosition {
+                position_id: None,
+                token_account: None,
+                ..
+            }
+            | ClientMessage::RequestExitSignal {
+                position_id: None,
+                token_account: None,
+                ..
+            } => (
+                ErrorCode::InvalidMessage,
+                "position_id or token_account is required".to_string(),
+            ),
+            _ => return Ok(()),
+        };
+        reject(code, message)
+    }
+
+    /// Checks a command and records its effect on the session when accepted.
+    pub fn apply(&mut self, msg: &ClientMessage) -> Result<(), Rejection> {
+        self.check(msg)?;
+        match msg {
+            ClientMessage::Configure {
+                wallet_pubkeys,
+                watch_wallets,
+                ..
+            } => {
+                self.wallets = Some(wallet_pubkeys.clone());
+                self.set_watch_wallets(watch_wallets);
+            }
+            ClientMessage::UpdateWallets { wallet_pubkeys, .. } => {
+                self.wallets = Some(wallet_pubkeys.clone());
+            }
+            ClientMessage::UpdateWatchWallets { watch_wallets, .. } => {
+                self.set_watch_wallets(watch_wallets);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn set_watch_wallets(&mut self, watch_wallets: &[WatchWalletEntryMsg]) {
+        self.auto_buy_wallets = watch_wallets
+            .iter()
+            .filter_map(|entry| {
+                let auto_buy = entry.auto_buy.as_ref()?;
+                Some((entry.pubkey.clone(), auto_buy.wallet_pubkey.clone()))
+            })
+            .collect();
+    }
+
+    fn check_wallets(&self, wallets: &[Pubkey58]) -> Option<(ErrorCode, String)> {
+        if wallets.is_empty() {
+            return Some((
+                ErrorCode::InvalidMessage,
+                "wallet_pubkeys must not be empty".to_string(),
+            ));
+        }
+        self.check_limit(
+            LimitKind::MaxWalletsPerSession,
+            ErrorCode::WalletsPerSessionExceeded,
+            wallets.len(),
+            "wallets",
+        )
+    }
+
+    fn check_watch_wallets(
+        &self,
+        watch_wallets: &[WatchWalletEntryMsg],
+        wallets: &[Pubkey58],
+    ) -> Option<(ErrorCode, String)> {
+        if let Some(error) = self.check_limit(
+            LimitKind::MaxWatchWalletsPerSession,
+            ErrorCode::WatchWalletsPerSessionExceeded,
+            watch_wallets.len(),
+            "watch wallets",
+        ) {
+            return Some(error);
+        }
+        let auto_buy_wallets = watch_wallets.iter().filter_map(|entry| {
+            let auto_buy = entry.auto_buy.as_ref()?;
+            Some((&entry.pubkey, &auto_buy.wallet_pubkey))
+        });
+        check_auto_buy_wallets(auto_buy_wallets, wallets)
+    }
+
+    fn check_limit(
+        &self,
+        kind: LimitKind,
+        code: ErrorCode,
+        count: usize,
+        what: &str,
+    ) -> Option<(ErrorCode, String)> {
+        let limit = self.limits.get(kind);
+        (limit > 0 && count > limit as usize).then(|| {
+            (
+                code,
+                format!("{count} {what} exceeds {} of {limit}", kind.field_name()),
+            )
+        })
+    }
+}
+
+/// Checks that every `(watched wallet, auto_buy wallet)` pair buys with a
+/// session wallet.
+fn check_auto_buy_wallets<'a>(
+    mut auto_buy_wallets: impl Iterator<Item = (&'a Pubkey58, &'a Pubkey58)>,
+    wallets: &[Pubkey58],
+) -> Option<(ErrorCode, String)> {
+    let (watched, wallet) = auto_buy_wallets.find(|(_, wallet)| !wallets.contains(wallet))?;
+    Some((
+        ErrorCode::InvalidMessage,
+        format!("auto_buy wallet {wallet} for watched wallet {watched} is not a session wallet"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AutoBuyConfigMsg, StrategyConfigMsg};
+
+    fn key(n: u8) -> Pubkey58 {
+        Pubkey58::from_bytes(&[n; 32])
+    }
+
+    fn limits() -> LimitsMsg {
+        LimitsMsg {
+            hi_capacity: 16,
+            pnl_flush_ms: 100,
+            max_positions_per_session: 16,
+            max_wallets_per_session: 2,
+            max_positions_per_wallet: 8,
+            max_sessions_per_api_key: 1,
+            max_watch_wallets_per_session: 1,
+        }
+    }
+
+    fn configure(wallets: Vec<Pubkey58>, watch_wallets: Vec<WatchWalletEntryMsg>) -> ClientMessage {
+        ClientMessage::Configure {
+            wallet_pubkeys: wallets,
+            strategy: StrategyConfigMsg::default(),
+            send_mode: None,
+            tip_lamports: None,
+            watch_wallets,
+            mirror_config: None,
+            request_id: Some(1),
+        }
+    }
+
+    fn watch(pubkey: u8, auto_buy_wallet: Option<u8>) -> WatchWalletEntryMsg {
+        WatchWalletEntryMsg {
+            pubkey: key(pubkey),
+            auto_buy: auto_buy_wallet.map(|wallet| AutoBuyConfigMsg {
+                wallet_pubkey: key(wallet),
+                amount_quote_units: 1_000,
+                amount_usd1_units: None,
+            }),
+            mirror_sell: false,
+        }
+    }
+
+    #[test]
+    fn rejects_commands_before_configure() {
+        let policy = SessionPolicy::new(limits());
+        let msg = ClientMessage::UpdateWallets {
+            wallet_pubkeys: vec![key(1)],
+            request_id: Some(4),
+        };
+        assert_eq!(
+            policy.check(&msg),
+            Err(Rejection {
+                code: ErrorCode::NotConfigured,
+                message: "session is not configured; send configure first".to_string(),
+                request_id: Some(4),
+            })
+        );
+    }
+
+    #[test]
+    fn enforces_wallet_limits() {
+        let mut policy = SessionPolicy::new(limits());
+        assert_eq!(
+            policy.apply(&configure(vec![key(1), key(2), key(3)], vec![])),
+            Err(Rejection {
+                code: ErrorCode::WalletsPerSessionExceeded,
+                message: "3 wallets exceeds max_wallets_per_session of 2".to_string(),
+                request_id: Some(1),
+            })
+        );
+        assert!(!policy.is_configured());
+
+        assert_eq!(policy.apply(&configure(vec![key(1)], vec![])), Ok(()));
+        assert_eq!(policy.wallets(), &[key(1)]);
+
+        let msg = ClientMessage::UpdateWallets {
+            wallet_pubkeys: vec![],
+            request_id: None,
+        };
+        let err = policy.check(&msg).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidMessage);
+        assert_eq!(
+            err.into_message(),
+            ServerMessage::Error {
+                code: ErrorCode::InvalidMessage,
+                message: "wallet_pubkeys must not be empty".to_string(),
+                request_id: None,
+            }
+        );
+    }
+
+    #[test]
+    fn enforces_watch_wallet_limit_and_auto_buy_wallets() {
+        let mut policy = SessionPolicy::new(limits());
+        policy.apply(&configure(vec![key(1)], vec![])).unwrap();
+
+        let too_many = ClientMessage::UpdateWatchWallets {
+            watch_wallets: vec![watch(5, None), watch(6, None)],
+            request_id: None,
+        };
+        assert!(matches!(
+            policy.check(&too_many),
+            Err(Rejection {
+                code: ErrorCode::WatchWalletsPerSessionExceeded,
+                ..
+            })
+        ));
+
+        let foreign_wallet = ClientMessage::UpdateWatchWallets {
+            watch_wallets: vec![watch(5, Some(9))],
+            request_id: None,
+        };
+        assert!(matches!(
+            policy.check(&foreign_wallet),
+            Err(Rejection {
+                code: ErrorCode::InvalidMessage,
+                ..
+            })
+        ));
+
+        let ok = ClientMessage::UpdateWatchWallets {
+            watch_wallets: vec![watch(5, Some(1))],
+            request_id: None,
+        };
+        assert_eq!(policy.check(&ok), Ok(()));
+    }
+
+    #[test]
+    fn update_wallets_keeps_auto_buy_wallets_in_the_session() {
+        let mut policy = SessionPolicy::new(limits());
+        policy
+            .apply(&configure(vec![key(1), key(2)], vec![watch(5, Some(2))]))
+            .unwrap();
+
+        let drop_auto_buy_wallet = ClientMessage::UpdateWallets {
+            wallet_pubkeys: vec![key(1)],
+            request_id: Some(3),
+        };
+        let err = policy.apply(&drop_auto_buy_wallet).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidMessage);
+        assert_eq!(err.request_id, Some(3));
+        assert_eq!(policy.wallets(), &[key(1), key(2)]);
+
+        let unwatch = ClientMessage::UpdateWatchWallets {
+            watch_wallets: vec![watch(5, None)],
+            request_id: None,
+        };
+        policy.apply(&unwatch).unwrap();
+        assert_eq!(policy.apply(&drop_auto_buy_wallet), Ok(()));
+    }
+
+    #[test]
+    fn zero_limits_are_not_enforced() {
+        let mut limits = limits();
+        limits.max_wallets_per_session = 0;
+        let policy = SessionPolicy::new(limits);
+        let wallets = (1..=20).map(key).collect();
+        assert_eq!(policy.check(&configure(wallets, vec![])), Ok(()));
+    }
+
+    #[test]
+    fn rejects_invalid_strategy_and_missing_position() {
+        let mut policy = SessionPolicy::new(limits());
+        policy.apply(&configure(vec![key(1)], vec![])).unwrap();
+
+        let strategy = ClientMessage::UpdateStrategy {
+            strategy: StrategyConfigMsg {
+                stop_loss_pct: f64::INFINITY,
+                ..Default::default()
+            },
+            request_id: None,
+        };
+        assert!(matches!(
+            policy.check(&strategy),
+            Err(Rejection {
+                code: ErrorCode::InvalidStrategy,
+                ..
+            })
+        ));
+
+        let close = ClientMessage::ClosePosition {
+            position_id: None,
+            token_account: None,
+            request_id: None,
+        };
+        assert!(matches!(
+            policy.check(&close),
+            Err(Rejection {
+                code: ErrorCode::InvalidMessage,
+                ..
+            })
+        ));
+    }
+}
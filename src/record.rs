@@ -0,0 +1,424 @@
+//! Session capture and replay in NDJSON.
+//!
+//! [`Recorder`] writes one JSON object per line for every message exchanged
+//! with the server, stamped with milliseconds since the recorder was created:
+//!
+//! ```text
+//! {"direction":"outbound","at_ms":0,"msg":{"type":"configure",...}}
+//! {"direction":"inbound","at_ms":41,"msg":{"type":"hello_ok",...}}
+//! ```
+//!
+//! [`Replayer`] reads a capture back and yields its entries in order, either
+//! paced like the original session (optionally sped up) or stepwise with no
+//! waiting, which makes replays deterministic. Inbound messages can be fed to
+//! [`SessionState`](crate::state::SessionState) or
+//! [`Evaluator`](crate::strategy::Evaluator) to reproduce what a client saw.
+//!
+//! ```
+//! use lasersell_stream_proto::record::{Recorder, ReplaySpeed, Replayer};
+//! use lasersell_stream_proto::ServerMessage;
+//!
+//! let mut recorder = Recorder::new(Vec::new());
+//! recorder.inbound(&ServerMessage::Pong { server_time_ms: 1 })?;
+//! let capture = recorder.into_inner();
+//!
+//! let replayer = Replayer::from_reader(capture.as_slice(), ReplaySpeed::Stepwise)?;
+//! let inbound: Vec<ServerMessage> = replayer.filter_map(|entry| entry.into_inbound()).collect();
+//! assert_eq!(inbound, [ServerMessage::Pong { server_time_ms: 1 }]);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ClientMessage, ServerMessage};
+
+/// One line of a capture.
+// Most entries are inbound, so boxing the server message would not make a
+// capture smaller.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "direction", rename_all = "snake_case")]
+pub enum RecordEntry {
+    /// A message received from the server.
+    Inbound {
+        /// Milliseconds since recording started.
+        at_ms: u64,
+        /// The received message.
+        msg: ServerMessage,
+    },
+    /// A command sent to the server.
+    Outbound {
+        /// Milliseconds since recording started.
+        at_ms: u64,
+        /// The sent command.
+        msg: ClientMessage,
+    },
+}
+
+impl RecordEntry {
+    /// Milliseconds since recording started.
+    pub fn at_ms(&self) -> u64 {
+        match self {
+            RecordEntry::Inbound { at_ms, .. } | RecordEntry::Outbound { at_ms, .. } => *at_ms,
+        }
+    }
+
+    /// The server message, for inbound entries.
+    pub fn inbound(&self) -> Option<&ServerMessage> {
+        match self {
+            RecordEntry::Inbound { msg, .. } => Some(msg),
+            RecordEntry::Outbound { .. } => None,
+        }
+    }
+
+    /// Consumes the entry and returns the server message, for inbound entries.
+    pub fn into_inbound(self) -> Option<ServerMessage> {
+        match self {
+            RecordEntry::Inbound { msg, .. } => Some(msg),
+            RecordEntry::Outbound { .. } => None,
+        }
+    }
+
+    /// The client command, for outbound entries.
+    pub fn outbound(&self) -> Option<&ClientMessage> {
+        match self {
+            RecordEntry::Outbound { msg, .. } => Some(msg),
+            RecordEntry::Inbound { .. } => None,
+        }
+    }
+}
+
+/// Writes a capture as NDJSON.
+#[derive(Debug)]
+pub struct Recorder<W> {
+    writer: W,
+    started: Instant,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Starts recording into `writer`; timestamps count from now.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            started: Instant::now(),
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    /// Records a message received from the server.
+    pub fn inbound(&mut self, msg: &ServerMessage) -> io::Result<()> {
+        self.record(&RecordEntry::Inbound {
+            at_ms: self.now_ms(),
+            msg: msg.clone(),
+        })
+    }
+
+    /// Records a command sent to the server.
+    pub fn outbound(&mut self, msg: &ClientMessage) -> io::Result<()> {
+        self.record(&RecordEntry::Outbound {
+            at_ms: self.now_ms(),
+            msg: msg.clone(),
+        })
+    }
+
+    /// Writes an entry with its own timestamp.
+    pub fn record(&mut self, entry: &RecordEntry) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, entry)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Error returned when a capture cannot be read.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Reading the capture failed.
+    Io(io::Error),
+    /// A line is not a valid entry.
+    Parse {
+        /// 1-based line number.
+        line: usize,
+        /// The JSON error.
+        source: serde_json::Error,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "failed to read capture: {err}"),
+            ReplayError::Parse { line, source } => {
+                write!(f, "invalid entry on line {line}: {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReplayError::Io(err) => Some(err),
+            ReplayError::Parse { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+/// Reads every entry of a capture. Blank lines are skipped.
+pub fn read_capture(reader: impl BufRead) -> Result<Vec<RecordEntry>, ReplayError> {
+    let mut entries = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|source| ReplayError::Parse {
+            line: index + 1,
+            source,
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Pace at which a [`Replayer`] yields entries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// With the gaps of the original session.
+    RealTime,
+    /// With the original gaps divided by the factor.
+    Accelerated(f64),
+    /// Without waiting; the consumer decides when to take the next entry.
+    Stepwise,
+}
+
+/// Yields the entries of a capture at a chosen [`ReplaySpeed`].
+///
+/// As an [`Iterator`], `next()` sleeps the current thread until the entry is
+/// due. Async consumers should instead wait for [`Replayer::delay`] on their
+/// runtime and then call [`Replayer::step`].
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    entries: std::vec::IntoIter<RecordEntry>,
+    speed: ReplaySpeed,
+    /// When the first entry was yielded, and its timestamp.
+    origin: Option<(Instant, u64)>,
+}
+
+impl Replayer {
+    /// Replays `entries` in order.
+    pub fn new(entries: Vec<RecordEntry>, speed: ReplaySpeed) -> Self {
+        Self {
+            entries: entries.into_iter(),
+            speed,
+            origin: None,
+        }
+    }
+
+    /// Reads a capture and replays it.
+    pub fn from_reader(reader: impl BufRead, speed: ReplaySpeed) -> Result<Self, ReplayError> {
+        Ok(Self::new(read_capture(reader)?, speed))
+    }
+
+    /// Entries not yet yielded.
+    pub fn remaining(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Time until the next entry is due; zero when stepping or finished.
+    pub fn delay(&self) -> Duration {
+        let factor = match self.speed {
+            ReplaySpeed::RealTime => 1.0,
+            ReplaySpeed::Accelerated(factor) if factor > 0.0 => factor,
+            ReplaySpeed::Accelerated(_) | ReplaySpeed::Stepwise => return Duration::ZERO,
+        };
+        let (Some((started, first_ms)), Some(next)) =
+            (self.origin, self.entries.as_slice().first())
+        else {
+            return Duration::ZERO;
+        };
+        // A tiny factor stretches the gap past what `Duration` holds.
+        let offset_ms = next.at_ms().saturating_sub(first_ms);
+        let offset = Duration::try_from_secs_f64(offset_ms as f64 / 1_000.0 / factor)
+            .unwrap_or(Duration::MAX);
+        started.checked_add(offset).map_or(Duration::MAX, |due| {
+            due.saturating_duration_since(Instant::now())
+        })
+    }
+
+    /// Yields the next entry without waiting.
+    pub fn step(&mut self) -> Option<RecordEntry> {
+        let entry = self.entries.next()?;
+        self.origin
+            .get_or_insert_with(|| (Instant::now(), entry.at_ms()));
+        Some(entry)
+    }
+
+    /// Feeds every remaining entry to `consumer`, pacing as configured.
+    pub fn replay(self, mut consumer: impl FnMut(RecordEntry)) {
+        for entry in self {
+            consumer(entry);
+        }
+    }
+}
+
+impl Iterator for Replayer {
+    type Item = RecordEntry;
+
+    fn next(&mut self) -> Option<RecordEntry> {
+        let delay = self.delay();
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+        self.step()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use crate::state::SessionState;
+
+    fn capture() -> Vec<RecordEntry> {
+        let mut entries = Vec::new();
+        for (i, msg) in fixtures::client_messages().into_iter().enumerate() {
+            entries.push(RecordEntry::Outbound {
+                at_ms: i as u64 * 10,
+                msg,
+            });
+        }
+        for (i, msg) in fixtures::server_messages().into_iter().enumerate() {
+            entries.push(RecordEntry::Inbound {
+                at_ms: 1_000 + i as u64 * 10,
+                msg,
+            });
+        }
+        entries
+    }
+
+    #[test]
+    fn capture_round_trips_as_ndjson() {
+        let mut recorder = Recorder::new(Vec::new());
+        for entry in capture() {
+            recorder.record(&entry).expect("record");
+        }
+        recorder
+            .inbound(&ServerMessage::Pong { server_time_ms: 5 })
+            .expect("record");
+        let bytes = recorder.into_inner();
+
+        let text = String::from_utf8(bytes.clone()).expect("utf-8");
+        assert!(text
+            .lines()
+            .next()
+            .unwrap()
+            .starts_with(r#"{"direction":"outbound","at_ms":0,"msg":{"type":"hello""#));
+        let mut read = read_capture(bytes.as_slice()).expect("read");
+        assert_eq!(
+            read.pop().and_then(RecordEntry::into_inbound),
+            Some(ServerMessage::Pong { server_time_ms: 5 })
+        );
+        assert_eq!(read, capture());
+    }
+
+    #[test]
+    fn reports_the_line_of_invalid_entries() {
+        let text = "{\"direction\":\"inbound\",\"at_ms\":0,\"msg\":{\"type\":\"pong\",\"server_time_ms\":1}}\n\n{\"direction\":\"sideways\"}\n";
+        match read_capture(text.as_bytes()) {
+            Err(ReplayError::Parse { line, .. }) => assert_eq!(line, 3),
+            other => panic!("expected parse error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn stepwise_replay_feeds_session_state() {
+        let mut live = SessionState::new();
+        for msg in fixtures::server_messages() {
+            live.apply(&msg);
+        }
+
+        let mut replayed = SessionState::new();
+        let mut replayer = Replayer::new(capture(), ReplaySpeed::Stepwise);
+        assert_eq!(replayer.delay(), Duration::ZERO);
+        while let Some(entry) = replayer.step() {
+            if let Some(msg) = entry.inbound() {
+                replayed.apply(msg);
+            }
+        }
+        assert_eq!(replayer.remaining(), 0);
+        assert_eq!(
+            replayed.positions().collect::<Vec<_>>(),
+            live.positions().collect::<Vec<_>>()
+        );
+        assert_eq!(replayed.session_id(), live.session_id());
+    }
+
+    #[test]
+    fn paced_replay_keeps_the_original_gaps() {
+        let pong = |at_ms| RecordEntry::Inbound {
+            at_ms,
+            msg: ServerMessage::Pong {
+                server_time_ms: at_ms,
+            },
+        };
+        let entries = vec![pong(1_000), pong(1_030), pong(1_060)];
+
+        let started = Instant::now();
+        let mut seen = Vec::new();
+        Replayer::new(entries.clone(), ReplaySpeed::RealTime).replay(|entry| seen.push(entry));
+        assert!(started.elapsed() >= Duration::from_millis(60));
+        assert_eq!(seen, entries);
+
+        let started = Instant::now();
+        let count = Replayer::new(entries, ReplaySpeed::Accelerated(1_000.0)).count();
+        assert_eq!(count, 3);
+        assert!(started.elapsed() < Duration::from_millis(60));
+    }
+
+    #[test]
+    fn tiny_speed_factors_saturate_the_delay() {
+        let pong = |at_ms| RecordEntry::Inbound {
+            at_ms,
+            msg: ServerMessage::Pong {
+                server_time_ms: at_ms,
+            },
+        };
+        let delay = |factor| {
+            let mut replayer = Replayer::new(
+                vec![pong(1_000), pong(1_030)],
+                ReplaySpeed::Accelerated(factor),
+            );
+            replayer.step();
+            replayer.delay()
+        };
+        assert!(delay(1e-20) > Duration::from_secs(1 << 60));
+        assert_eq!(delay(1e-30), Duration::MAX);
+    }
+}
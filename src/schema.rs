@@ -0,0 +1,252 @@
+//! JSON Schema for the wire protocol (`schema` feature).
+//!
+//! Every wire type implements [`schemars::JsonSchema`]. [`bundle`] collects
+//! them into one self-contained document for generating clients in other
+//! languages. Schemas describe what this crate accepts: fields with defaults
+//! are optional, open enums list their known values but admit any string, and
+//! legacy aliases are included.
+
+use schemars::generate::SchemaSettings;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde_json::{Map, Value};
+
+use crate::{ClientMessage, ServerEnvelope, ServerFrame, ServerMessage, PROTOCOL_VERSION};
+
+/// Identifier of the bundle document.
+pub const BUNDLE_ID: &str = "https://github.com/lasersell/lasersell-stream-proto/schema.json";
+
+/// Client-to-server field aliases accepted for backward compatibility.
+///
+/// Each entry is `(message type, alias, canonical field)`; a `None` field
+/// means the alias is an alternative `type` value for the message.
+const CLIENT_ALIASES: &[(&str, &str, Option<&str>)] = &[
+    ("configure", "wallet_pubkey", Some("wallet_pubkeys")),
+    ("request_exit_signal", "sell_now", None),
+];
+
+fn generator() -> SchemaGenerator {
+    SchemaSettings::draft2020_12().into_generator()
+}
+
+/// Returns the schema of a single type, with its definitions inlined under `$defs`.
+pub fn schema_for<T: JsonSchema>() -> Schema {
+    generator().into_root_schema_for::<T>()
+}
+
+/// Returns one document describing every client and server frame.
+///
+/// The root accepts either a `ClientMessage` or a `ServerFrame`; every type
+/// is also available under `$defs` by its Rust name.
+pub fn bundle() -> Value {
+    let mut generator = generator();
+    let client = generator.subschema_for::<ClientMessage>();
+    let frame = generator.subschema_for::<ServerFrame>();
+    generator.subschema_for::<ServerMessage>();
+    generator.subschema_for::<ServerEnvelope>();
+    let mut defs: Map<String, Value> = generator.take_definitions(true);
+    if let Some(client) = defs.get_mut("ClientMessage") {
+        add_client_aliases(client);
+    }
+
+    let mut root = json_schema!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": BUNDLE_ID,
+        "title": "LaserSell stream protocol",
+        "x-protocol-version": PROTOCOL_VERSION,
+        "anyOf": [client, frame],
+    });
+    root.insert("$defs".to_string(), Value::Object(defs));
+    root.to_value()
+}
+
+/// Schema of `wallet_pubkeys`, which also accepts a single pubkey.
+pub(crate) fn wallet_pubkeys(generator: &mut SchemaGenerator) -> Schema {
+    let pubkey = generator.subschema_for::<crate::Pubkey58>();
+    if generator.contract().is_serialize() {
+        return json_schema!({ "type": "array", "items": pubkey });
+    }
+    json_schema!({
+        "anyOf": [
+            { "type": "array", "items": pubkey },
+            pubkey,
+        ]
+    })
+}
+
+/// Adds the legacy aliases from [`CLIENT_ALIASES`] to the `ClientMessage`
+/// variant schemas.
+fn add_client_aliases(client: &mut Value) {
+    let Some(variants) = client.get_mut("oneOf").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for variant in variants {
+        let Some(tag) = variant
+            .pointer("/properties/type/const")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        for (_, alias, field) in CLIENT_ALIASES.iter().filter(|(ty, ..)| *ty == tag) {
+            match field {
+                Some(field) => add_field_alias(variant, alias, field),
+                None => {
+                    variant["properties"]["type"] = json_schema!({
+                        "type": "string",
+                        "enum": [tag, alias],
+                    })
+                    .to_value();
+                }
+            }
+        }
+    }
+}
+
+/// Accepts `alias` in place of `field`: the alias gets the field's schema and
+/// exactly one of the two is required.
+fn add_field_alias(variant: &mut Value, alias: &str, field: &str) {
+    let Some(properties) = variant.get_mut("properties").and_then(Value::as_object_mut) else {
+        return;
+    };
+    let Some(schema) = properties.get(field).cloned() else {
+        return;
+    };
+    properties.insert(alias.to_string(), schema);
+    if let Some(required) = variant.get_mut("required").and_then(Value::as_array_mut) {
+        required.retain(|name| name != field);
+    }
+    variant["oneOf"] = serde_json::json!([
+        { "required": [field] },
+        { "required": [alias] },
+    ]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn variant<'a>(bundle: &'a Value, def: &str, tag: &str) -> &'a Value {
+        bundle["$defs"][def]["oneOf"]
+            .as_array()
+            .expect("oneOf")
+            .iter()
+            .find(|variant| {
+                let ty = &variant["properties"]["type"];
+                ty["const"] == tag
+                    || ty["enum"]
+                        .as_array()
+                        .is_some_and(|e| e.contains(&tag.into()))
+            })
+            .unwrap_or_else(|| panic!("no {def} variant for {tag}"))
+    }
+
+    #[test]
+    fn bundle_covers_every_variant_and_msg_struct() {
+        let bundle = bundle();
+        for msg in fixtures::client_messages() {
+            let value = serde_json::to_value(&msg).unwrap();
+            variant(&bundle, "ClientMessage", value["type"].as_str().unwrap());
+        }
+        for msg in fixtures::server_messages() {
+            let value = serde_json::to_value(&msg).unwrap();
+            variant(&bundle, "ServerMessage", value["type"].as_str().unwrap());
+        }
+        for def in [
+            "StrategyConfigMsg",
+            "TakeProfitLevelMsg",
+            "WatchWalletEntryMsg",
+            "AutoBuyConfigMsg",
+            "MirrorConfigMsg",
+            "LimitsMsg",
+            "MarketContextMsg",
+            "MarketTypeMsg",
+            "PumpFunContextMsg",
+            "PumpSwapContextMsg",
+            "MeteoraDbcContextMsg",
+            "MeteoraDammV2ContextMsg",
+            "RaydiumLaunchpadContextMsg",
+            "RaydiumCpmmContextMsg",
+            "SlippageBandMsg",
+            "ServerEnvelope",
+            "Pubkey58",
+            "ExitReason",
+        ] {
+            assert!(bundle["$defs"].get(def).is_some(), "missing {def}");
+        }
+    }
+
+    #[test]
+    fn optional_fields_are_not_required() {
+        let bundle = bundle();
+        let pnl = variant(&bundle, "ServerMessage", "pnl_update");
+        let required = pnl["required"].as_array().expect("required");
+        assert!(required.contains(&"position_id".into()));
+        assert!(required.contains(&"type".into()));
+        assert!(!required.contains(&"token_price_quote".into()));
+        assert!(!required.contains(&"watched".into()));
+    }
+
+    #[test]
+    fn legacy_aliases_are_described() {
+        let bundle = bundle();
+        let exit = variant(&bundle, "ClientMessage", "sell_now");
+        assert_eq!(
+            exit["properties"]["type"]["enum"],
+            serde_json::json!(["request_exit_signal", "sell_now"])
+        );
+
+        let configure = variant(&bundle, "ClientMessage", "configure");
+        assert_eq!(
+            configure["properties"]["wallet_pubkey"]["anyOf"][1]["$ref"],
+            "#/$defs/Pubkey58"
+        );
+        assert!(!configure["required"]
+            .as_array()
+            .unwrap()
+            .contains(&"wallet_pubkeys".into()));
+    }
+
+    #[test]
+    fn client_aliases_are_accepted_and_described() {
+        let bundle = bundle();
+        for (ty, alias, field) in CLIENT_ALIASES {
+            let msg = fixtures::client_messages()
+                .into_iter()
+                .find(|msg| serde_json::to_value(msg).unwrap()["type"] == *ty)
+                .unwrap_or_else(|| panic!("no fixture for {ty}"));
+            let mut value = serde_json::to_value(&msg).unwrap();
+            match field {
+                Some(field) => {
+                    let moved = value[field].take();
+                    let object = value.as_object_mut().unwrap();
+                    object.remove(*field);
+                    object.insert(alias.to_string(), moved);
+                    assert!(
+                        variant(&bundle, "ClientMessage", ty)["properties"]
+                            .get(alias)
+                            .is_some(),
+                        "bundle does not list {alias}"
+                    );
+                }
+                None => {
+                    value["type"] = (*alias).into();
+                    variant(&bundle, "ClientMessage", alias);
+                }
+            }
+            let decoded: ClientMessage = serde_json::from_value(value)
+                .unwrap_or_else(|err| panic!("alias {alias} rejected: {err}"));
+            assert_eq!(decoded, msg);
+        }
+    }
+
+    #[test]
+    fn open_enums_admit_unknown_values() {
+        let schema = schema_for::<crate::TradeSide>().to_value();
+        assert_eq!(
+            schema["anyOf"][0]["enum"],
+            serde_json::json!(["buy", "sell"])
+        );
+        assert_eq!(schema["anyOf"][1]["type"], "string");
+    }
+}
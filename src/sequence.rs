@@ -0,0 +1,317 @@
+//! Sequenced server frames and gap detection.
+//!
+//! Servers that negotiate [`Capability::Sequenced`](crate::Capability::Sequenced)
+//! wrap every event in a [`ServerEnvelope`] carrying a per-session sequence
+//! number starting at 1. After a reconnect the client sends
+//! `ClientMessage::Resume` with the last sequence number it processed so the
+//! server can replay what was missed.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ClientMessage, ServerMessage};
+
+/// A server event tagged with its position in the session's event stream.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ServerEnvelope {
+    /// Per-session sequence number, starting at 1.
+    pub seq: u64,
+    /// Session the sequence number belongs to.
+    pub session_id: u64,
+    /// The wrapped event.
+    pub payload: ServerMessage,
+}
+
+/// A server frame that is either sequenced or a bare [`ServerMessage`].
+///
+/// Bare messages are what servers without sequencing send.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum ServerFrame {
+    /// Event wrapped in a sequenced envelope.
+    Sequenced(ServerEnvelope),
+    /// Event without sequencing.
+    Plain(ServerMessage),
+}
+
+impl ServerFrame {
+    /// Parses a JSON string into a [`ServerFrame`].
+    pub fn from_text(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    /// Serializes this frame into its JSON wire representation.
+    pub fn to_text(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Returns the sequence number, if this frame is sequenced.
+    pub fn seq(&self) -> Option<u64> {
+        match self {
+            ServerFrame::Sequenced(envelope) => Some(envelope.seq),
+            ServerFrame::Plain(_) => None,
+        }
+    }
+
+    /// Returns the wrapped message.
+    pub fn message(&self) -> &ServerMessage {
+        match self {
+            ServerFrame::Sequenced(envelope) => &envelope.payload,
+            ServerFrame::Plain(msg) => msg,
+        }
+    }
+
+    /// Consumes the frame and returns the wrapped message.
+    pub fn into_message(self) -> ServerMessage {
+        match self {
+            ServerFrame::Sequenced(envelope) => envelope.payload,
+            ServerFrame::Plain(msg) => msg,
+        }
+    }
+}
+
+impl From<ServerMessage> for ServerFrame {
+    fn from(msg: ServerMessage) -> Self {
+        ServerFrame::Plain(msg)
+    }
+}
+
+impl From<ServerEnvelope> for ServerFrame {
+    fn from(envelope: ServerEnvelope) -> Self {
+        ServerFrame::Sequenced(envelope)
+    }
+}
+
+/// Outcome of feeding a sequence number to a [`SequenceTracker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceEvent {
+    /// The frame is the next one expected.
+    InOrder,
+    /// Frames in `missing` were never received.
+    Gap {
+        /// Sequence numbers that were skipped.
+        missing: Range<u64>,
+    },
+    /// The frame fills part of a gap reported earlier and has not been
+    /// processed yet.
+    Late,
+    /// The frame was already processed and should be ignored.
+    Duplicate,
+    /// The frame belongs to a new session; tracking restarted from it.
+    SessionChanged {
+        /// Session that was tracked before.
+        previous: u64,
+    },
+}
+
+/// Tracks sequence numbers of one session and reports gaps and duplicates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SequenceTracker {
+    session_id: Option<u64>,
+    last_seq: u64,
+    /// Reported gaps whose frames have not all arrived.
+    missing: Vec<Range<u64>>,
+}
+
+impl SequenceTracker {
+    /// Creates a tracker that expects sequence 1 of the first session seen.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a tracker that continues after `last_seq` of `session_id`.
+    pub fn resume(session_id: u64, last_seq: u64) -> Self {
+        Self {
+            session_id: Some(session_id),
+            last_seq,
+            missing: Vec::new(),
+        }
+    }
+
+    /// Session currently being tracked.
+    pub fn session_id(&self) -> Option<u64> {
+        self.session_id
+    }
+
+    /// Highest sequence number processed so far (0 when none).
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Records a sequence number and classifies it.
+    pub fn observe(&mut self, session_id: u64, seq: u64) -> SequenceEvent {
+        match self.session_id {
+            Some(current) if current != session_id => {
+                self.session_id = Some(session_id);
+                self.last_seq = seq;
+                self.missing.clear();
+                return SequenceEvent::SessionChanged { previous: current };
+            }
+            _ => self.session_id = Some(session_id),
+        }
+
+        // Once `u64::MAX` is processed no later frame can be newer.
+        let Some(expected) = self.last_seq.checked_add(1) else {
+            return self.earlier(seq);
+        };
+        if seq < expected {
+            return self.earlier(seq);
+        }
+        self.last_seq = seq;
+        if seq == expected {
+            SequenceEvent::InOrder
+        } else {
+            self.missing.push(expected..seq);
+            SequenceEvent::Gap {
+                missing: expected..seq,
+            }
+        }
+    }
+
+    /// Classifies a frame at or below `last_seq`.
+    fn earlier(&mut self, seq: u64) -> SequenceEvent {
+        let Some(index) = self.missing.iter().position(|gap| gap.contains(&seq)) else {
+            return SequenceEvent::Duplicate;
+        };
+        let gap = self.missing.remove(index);
+        for rest in [gap.start..seq, seq + 1..gap.end] {
+            if !rest.is_empty() {
+                self.missing.push(rest);
+            }
+        }
+        SequenceEvent::Late
+    }
+
+    /// Records a frame; returns `None` for unsequenced frames.
+    pub fn observe_frame(&mut self, frame: &ServerFrame) -> Option<SequenceEvent> {
+        match frame {
+            ServerFrame::Sequenced(envelope) => {
+                Some(self.observe(envelope.session_id, envelope.seq))
+            }
+            ServerFrame::Plain(_) => None,
+        }
+    }
+
+    /// Highest sequence number below which every frame has been received.
+    ///
+    /// Equals [`last_seq`](Self::last_seq) unless a reported gap is still
+    /// unfilled, in which case it stops just before the earliest one.
+    pub fn contiguous_seq(&self) -> u64 {
+        self.missing
+            .iter()
+            .map(|gap| gap.start - 1)
+            .min()
+            .unwrap_or(self.last_seq)
+    }
+
+    /// Builds the `Resume` command for the tracked session, if any.
+    ///
+    /// Resumes from [`contiguous_seq`](Self::contiguous_seq) so the server
+    /// replays frames lost in unfilled gaps; frames after the gap that were
+    /// already processed come back as [`SequenceEvent::Duplicate`].
+    pub fn resume_message(&self) -> Option<ClientMessage> {
+        self.session_id.map(|session_id| ClientMessage::Resume {
+            session_id,
+            last_seq: self.contiguous_seq(),
+            request_id: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pong(server_time_ms: u64) -> ServerMessage {
+        ServerMessage::Pong { server_time_ms }
+    }
+
+    #[test]
+    fn frame_decodes_enveloped_and_plain_forms() {
+        let plain = ServerFrame::from_text(r#"{"type":"pong","server_time_ms":5}"#).expect("plain");
+        assert_eq!(plain, ServerFrame::Plain(pong(5)));
+        assert_eq!(plain.seq(), None);
+
+        let raw = r#"{"seq":3,"session_id":9,"payload":{"type":"pong","server_time_ms":5}}"#;
+        let frame = ServerFrame::from_text(raw).expect("sequenced");
+        assert_eq!(frame.seq(), Some(3));
+        assert_eq!(frame.message(), &pong(5));
+        assert_eq!(frame.to_text().expect("serialize"), raw);
+    }
+
+    #[test]
+    fn tracker_reports_gaps_and_duplicates() {
+        let mut tracker = SequenceTracker::new();
+        assert_eq!(tracker.observe(9, 1), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(9, 2), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(9, 5), SequenceEvent::Gap { missing: 3..5 });
+        assert_eq!(tracker.observe(9, 4), SequenceEvent::Late);
+        assert_eq!(tracker.observe(9, 4), SequenceEvent::Duplicate);
+        assert_eq!(tracker.observe(9, 2), SequenceEvent::Duplicate);
+        assert_eq!(tracker.observe(9, 6), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(9, 3), SequenceEvent::Late);
+        assert_eq!(tracker.last_seq(), 6);
+        assert_eq!(
+            tracker.resume_message(),
+            Some(ClientMessage::Resume {
+                session_id: 9,
+                last_seq: 6,
+                request_id: None,
+            })
+        );
+    }
+
+    #[test]
+    fn tracker_resumes_and_detects_session_change() {
+        let mut tracker = SequenceTracker::resume(9, 10);
+        assert_eq!(tracker.observe(9, 11), SequenceEvent::InOrder);
+        assert_eq!(
+            tracker.observe(12, 1),
+            SequenceEvent::SessionChanged { previous: 9 }
+        );
+        assert_eq!(tracker.session_id(), Some(12));
+
+        let frame = ServerFrame::Sequenced(ServerEnvelope {
+            seq: 2,
+            session_id: 12,
+            payload: pong(1),
+        });
+        assert_eq!(tracker.observe_frame(&frame), Some(SequenceEvent::InOrder));
+        assert_eq!(tracker.observe_frame(&ServerFrame::Plain(pong(1))), None);
+    }
+
+    #[test]
+    fn resume_replays_unfilled_gaps() {
+        let mut tracker = SequenceTracker::new();
+        assert_eq!(tracker.observe(9, 1), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(9, 2), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(9, 5), SequenceEvent::Gap { missing: 3..5 });
+        assert_eq!(tracker.observe(9, 6), SequenceEvent::InOrder);
+
+        // Disconnected before 3 and 4 arrived: resume right before the gap.
+        let Some(ClientMessage::Resume { last_seq, .. }) = tracker.resume_message() else {
+            panic!("expected resume");
+        };
+        assert_eq!(last_seq, 2);
+
+        // The replay fills the gap; what was already seen is a duplicate.
+        assert_eq!(tracker.observe(9, 3), SequenceEvent::Late);
+        assert_eq!(tracker.contiguous_seq(), 3);
+        assert_eq!(tracker.observe(9, 4), SequenceEvent::Late);
+        assert_eq!(tracker.observe(9, 5), SequenceEvent::Duplicate);
+        assert_eq!(tracker.contiguous_seq(), 6);
+        assert_eq!(tracker.observe(9, 7), SequenceEvent::InOrder);
+    }
+
+    #[test]
+    fn tracker_stops_at_the_end_of_the_sequence_space() {
+        let mut tracker = SequenceTracker::resume(9, u64::MAX - 1);
+        assert_eq!(tracker.observe(9, u64::MAX), SequenceEvent::InOrder);
+        assert_eq!(tracker.observe(9, u64::MAX), SequenceEvent::Duplicate);
+        assert_eq!(tracker.observe(9, 1), SequenceEvent::Duplicate);
+        assert_eq!(tracker.last_seq(), u64::MAX);
+    }
+}
@@ -0,0 +1,769 @@
+//! Client-side portfolio model built from [`ServerMessage`]s.
+//!
+//! [`SessionState`] folds the server's event stream into the set of open
+//! positions, their latest PnL, liquidity and trade data, and wallet token
+//! balances. Each call to [`SessionState::apply`] reports what changed,
+//! including events that do not fit the current state.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    ExitReason, LimitsMsg, LiquidityTrend, MarketContextMsg, Pubkey58, ServerMessage,
+    SlippageBandMsg, TradeSide,
+};
+
+/// The most recent trade observed on a position's pool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastTrade {
+    /// Trade direction.
+    pub side: TradeSide,
+    /// Price per token in quote units.
+    pub price_quote: u64,
+    /// Unix timestamp in milliseconds when the trade was observed.
+    pub time_ms: u64,
+}
+
+/// Tracked state of one open position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionState {
+    /// Internal position identifier.
+    pub position_id: u64,
+    /// Wallet pubkey associated with the position.
+    pub wallet_pubkey: Pubkey58,
+    /// Token mint pubkey.
+    pub mint: Pubkey58,
+    /// Token account pubkey.
+    pub token_account: Pubkey58,
+    /// Optional token program pubkey.
+    pub token_program: Option<Pubkey58>,
+    /// Position token amount in native units.
+    pub tokens: u64,
+    /// Entry cost in quote units.
+    pub entry_quote_units: u64,
+    /// Market metadata, when provided.
+    pub market_context: Option<MarketContextMsg>,
+    /// Human-readable token name.
+    pub token_name: Option<String>,
+    /// Token ticker symbol.
+    pub token_symbol: Option<String>,
+    /// Token decimal places for display.
+    pub token_decimals: Option<u8>,
+    /// Latest token price in quote units.
+    pub token_price_quote: Option<u64>,
+    /// Latest market cap in quote units.
+    pub market_cap_quote: Option<u64>,
+    /// Pool liquidity in quote units at time of open.
+    pub pool_liquidity_quote: Option<u64>,
+    /// Server timestamp when the position opened, in Unix milliseconds.
+    pub opened_at_ms: Option<u64>,
+    /// Slot of the latest token amount.
+    pub slot: u64,
+    /// True when this position belongs to a watched (copy-traded) wallet.
+    pub watched: bool,
+    /// Identifier of the mirror source that triggered this position.
+    pub mirror_source: Option<String>,
+    /// Latest profit/loss in quote units.
+    pub profit_units: Option<i64>,
+    /// Latest estimated proceeds in quote units.
+    pub proceeds_units: Option<u64>,
+    /// Server timestamp of the latest PnL update.
+    pub pnl_time_ms: Option<u64>,
+    /// Latest slippage bands.
+    pub liquidity_bands: Vec<SlippageBandMsg>,
+    /// Latest liquidity trend.
+    pub liquidity_trend: Option<LiquidityTrend>,
+    /// Latest trade on the pool.
+    pub last_trade: Option<LastTrade>,
+}
+
+/// Token balance of one wallet/mint pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceState {
+    /// Token amount in native units.
+    pub tokens: u64,
+    /// Slot the balance snapshot came from.
+    pub slot: u64,
+    /// Token account pubkey, when known.
+    pub token_account: Option<Pubkey58>,
+}
+
+/// An event that does not fit the tracked state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency {
+    /// An event referenced a position that is not open.
+    UnknownPosition {
+        /// Referenced position.
+        position_id: u64,
+        /// Wire `type` of the offending event.
+        event: &'static str,
+    },
+    /// `PositionOpened` arrived for a position that is already open.
+    DuplicateOpen {
+        /// Referenced position.
+        position_id: u64,
+    },
+    /// `PnlDelta` does not apply to the last PnL update of the position.
+    PnlDeltaBaseMismatch {
+        /// Referenced position.
+        position_id: u64,
+        /// `server_time_ms` of the last PnL update applied, if any.
+        expected: Option<u64>,
+        /// `base_server_time_ms` carried by the delta.
+        found: u64,
+    },
+}
+
+/// A change reported by [`SessionState::apply`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateChange {
+    /// A new session started; positions from earlier sessions were dropped.
+    SessionStarted {
+        /// Assigned session identifier.
+        session_id: u64,
+    },
+    /// A position was added.
+    PositionOpened {
+        /// New position.
+        position_id: u64,
+    },
+    /// A tracked position's market data or token amount changed.
+    PositionUpdated {
+        /// Updated position.
+        position_id: u64,
+    },
+    /// A position was removed.
+    PositionClosed {
+        /// Final state of the position.
+        position: Box<PositionState>,
+        /// Reason for the close event.
+        reason: ExitReason,
+    },
+    /// A wallet's token balance changed.
+    BalanceChanged {
+        /// Wallet pubkey the balance belongs to.
+        wallet_pubkey: Pubkey58,
+        /// Token mint pubkey.
+        mint: Pubkey58,
+        /// New token amount in native units.
+        tokens: u64,
+    },
+    /// The event did not fit the tracked state and was ignored.
+    Inconsistency(Inconsistency),
+}
+
+/// Portfolio model for one stream session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionState {
+    session_id: Option<u64>,
+    limits: Option<LimitsMsg>,
+    positions: BTreeMap<u64, PositionState>,
+    token_accounts: BTreeMap<Pubkey58, u64>,
+    balances: BTreeMap<(Pubkey58, Pubkey58), BalanceState>,
+}
+
+impl SessionState {
+    /// Creates an empty state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current session identifier.
+    pub fn session_id(&self) -> Option<u64> {
+        self.session_id
+    }
+
+    /// Limits announced in the latest `HelloOk`.
+    pub fn limits(&self) -> Option<&LimitsMsg> {
+        self.limits.as_ref()
+    }
+
+    /// Returns an open position.
+    pub fn position(&self, position_id: u64) -> Option<&PositionState> {
+        self.positions.get(&position_id)
+    }
+
+    /// Returns the open position holding a token account.
+    pub fn position_by_token_account(&self, token_account: &Pubkey58) -> Option<&PositionState> {
+        self.token_accounts
+            .get(token_account)
+            .and_then(|id| self.positions.get(id))
+    }
+
+    /// All open positions, ordered by position ID.
+    pub fn positions(&self) -> impl Iterator<Item = &PositionState> {
+        self.positions.values()
+    }
+
+    /// Open positions of the user's own wallets.
+    pub fn own_positions(&self) -> impl Iterator<Item = &PositionState> {
+        self.positions().filter(|position| !position.watched)
+    }
+
+    /// Open positions of watched (copy-traded) wallets.
+    pub fn watched_positions(&self) -> impl Iterator<Item = &PositionState> {
+        self.positions().filter(|position| position.watched)
+    }
+
+    /// Latest known balance of a wallet/mint pair.
+    pub fn balance(&self, wallet_pubkey: &Pubkey58, mint: &Pubkey58) -> Option<&BalanceState> {
+        self.balances.get(&(wallet_pubkey.clone(), mint.clone()))
+    }
+
+    /// Folds a server message into the state and reports what changed.
+    pub fn apply(&mut self, msg: &ServerMessage) -> Vec<StateChange> {
+        let mut changes = Vec::new();
+        match msg {
+            ServerMessage::HelloOk {
+                session_id, limits, ..
+            } => {
+                self.limits = Some(limits.clone());
+                if self.session_id != Some(*session_id) {
+                    self.session_id = Some(*session_id);
+                    self.positions.clear();
+                    self.token_accounts.clear();
+                    changes.push(StateChange::SessionStarted {
+                        session_id: *session_id,
+                    });
+                }
+            }
+            ServerMessage::PositionOpened {
+                position_id,
+                wallet_pubkey,
+                mint,
+                token_account,
+                token_program,
+                tokens,
+                entry_quote_units,
+                market_context,
+                token_name,
+                token_symbol,
+                token_decimals,
+                token_price_quote,
+                market_cap_quote,
+                pool_liquidity_quote,
+                opened_at_ms,
+                slot,
+                watched,
+                mirror_source,
+            } => {
+                if self.positions.contains_key(position_id) {
+                    changes.push(StateChange::Inconsistency(Inconsistency::DuplicateOpen {
+                        position_id: *position_id,
+                    }));
+                    return changes;
+                }
+                self.token_accounts
+                    .insert(token_account.clone(), *position_id);
+                self.positions.insert(
+                    *position_id,
+                    PositionState {
+                        position_id: *position_id,
+                        wallet_pubkey: wallet_pubkey.clone(),
+                        mint: mint.clone(),
+                        token_account: token_account.clone(),
+                        token_program: token_program.clone(),
+                        tokens: *tokens,
+                        entry_quote_units: *entry_quote_units,
+                        market_context: market_context.clone(),
+                        token_name: token_name.clone(),
+                        token_symbol: token_symbol.clone(),
+                        token_decimals: *token_decimals,
+                        token_price_quote: *token_price_quote,
+                        market_cap_quote: *market_cap_quote,
+                        pool_liquidity_quote: *pool_liquidity_quote,
+                        opened_at_ms: *opened_at_ms,
+                        slot: *slot,
+                        watched: *watched,
+                        mirror_source: mirror_source.clone(),
+                        profit_units: None,
+                        proceeds_units: None,
+                        pnl_time_ms: None,
+                        liquidity_bands: Vec::new(),
+                        liquidity_trend: None,
+                        last_trade: None,
+                    },
+                );
+                changes.push(StateChange::PositionOpened {
+                    position_id: *position_id,
+                });
+            }
+            ServerMessage::PnlUpdate {
+                position_id,
+                profit_units,
+                proceeds_units,
+                server_time_ms,
+                token_price_quote,
+                market_cap_quote,
+                ..
+            } => self.update(*position_id, "pnl_update", &mut changes, |position| {
+                position.profit_units = Some(*profit_units);
+                position.proceeds_units = Some(*proceeds_units);
+                position.pnl_time_ms = Some(*server_time_ms);
+                if token_price_quote.is_some() {
+                    position.token_price_quote = *token_price_quote;
+                }
+                if market_cap_quote.is_some() {
+                    position.market_cap_quote = *market_cap_quote;
+                }
+            }),
+            ServerMessage::PnlDelta {
+                position_id,
+                base_server_time_ms,
+                server_time_ms,
+                profit_units,
+                proceeds_units,
+                token_price_quote,
+                market_cap_quote,
+            } => {
+                let expected = self
+                    .positions
+                    .get(position_id)
+                    .and_then(|position| position.pnl_time_ms);
+                if self.positions.contains_key(position_id)
+                    && expected != Some(*base_server_time_ms)
+                {
+                    changes.push(StateChange::Inconsistency(
+                        Inconsistency::PnlDeltaBaseMismatch {
+                            position_id: *position_id,
+                            expected,
+                            found: *base_server_time_ms,
+                        },
+                    ));
+                    return changes;
+                }
+                self.update(*position_id, "pnl_delta", &mut changes, |position| {
+                    position.pnl_time_ms = Some(*server_time_ms);
+                    if profit_units.is_some() {
+                        position.profit_units = *profit_units;
+                    }
+                    if proceeds_units.is_some() {
+                        position.proceeds_units = *proceeds_units;
+                    }
+                    if token_price_quote.is_some() {
+                        position.token_price_quote = *token_price_quote;
+                    }
+                    if market_cap_quote.is_some() {
+                        position.market_cap_quote = *market_cap_quote;
+                    }
+                })
+            }
+            ServerMessage::LiquiditySnapshot {
+                position_id,
+                bands,
+                liquidity_trend,
+                ..
+            } => self.update(
+                *position_id,
+                "liquidity_snapshot",
+                &mut changes,
+                |position| {
+                    position.liquidity_bands = bands.clone();
+                    position.liquidity_trend = Some(liquidity_trend.clone());
+                },
+            ),
+            ServerMessage::TradeTick {
+                position_id,
+                time_ms,
+                side,
+                price_quote,
+                ..
+            } => self.update(*position_id, "trade_tick", &mut changes, |position| {
+                position.last_trade = Some(LastTrade {
+                    side: side.clone(),
+                    price_quote: *price_quote,
+                    time_ms: *time_ms,
+                });
+            }),
+            ServerMessage::BalanceUpdate {
+                wallet_pubkey,
+                mint,
+                token_account,
+                tokens,
+                slot,
+                ..
+            } => self.apply_balance(
+                wallet_pubkey,
+                mint,
+                token_account.as_ref(),
+                *tokens,
+                *slot,
+                &mut changes,
+            ),
+            ServerMessage::PositionClosed {
+                position_id,
+                reason,
+                ..
+            } => match self.positions.remove(position_id) {
+                Some(position) => {
+                    self.unlink_token_account(&position);
+                    changes.push(StateChange::PositionClosed {
+                        position: Box::new(position),
+                        reason: reason.clone(),
+                    });
+                }
+                None => changes.push(StateChange::Inconsistency(Inconsistency::UnknownPosition {
+                    position_id: *position_id,
+                    event: "position_closed",
+                })),
+            },
+            ServerMessage::ExitSignalWithTx { position_id, .. }
+                if !self.positions.contains_key(position_id) =>
+            {
+                changes.push(StateChange::Inconsistency(Inconsistency::UnknownPosition {
+                    position_id: *position_id,
+                    event: "exit_signal_with_tx",
+                }));
+            }
+            ServerMessage::Batch { messages } => {
+                for msg in messages {
+                    changes.extend(self.apply(msg));
+                }
+            }
+            _ => {}
+        }
+        changes
+    }
+
+    fn update(
+        &mut self,
+        position_id: u64,
+        event: &'static str,
+        changes: &mut Vec<StateChange>,
+        f: impl FnOnce(&mut PositionState),
+    ) {
+        match self.positions.get_mut(&position_id) {
+            Some(position) => {
+                f(position);
+                changes.push(StateChange::PositionUpdated { position_id });
+            }
+            None => changes.push(StateChange::Inconsistency(Inconsistency::UnknownPosition {
+                position_id,
+                event,
+            })),
+        }
+    }
+
+    /// Drops the token account lookup of a closed position, pointing it at
+    /// another open position on the same account if there is one.
+    fn unlink_token_account(&mut self, closed: &PositionState) {
+        let account = &closed.token_account;
+        if self.token_accounts.get(account) != Some(&closed.position_id) {
+            return;
+        }
+        match self
+            .positions
+            .values()
+            .find(|position| &position.token_account == account)
+        {
+            Some(other) => {
+                self.token_accounts
+                    .insert(account.clone(), other.position_id);
+            }
+            None => {
+                self.token_accounts.remove(account);
+            }
+        }
+    }
+
+    fn apply_balance(
+        &mut self,
+        wallet_pubkey: &Pubkey58,
+        mint: &Pubkey58,
+        token_account: Option<&Pubkey58>,
+        tokens: u64,
+        slot: u64,
+        changes: &mut Vec<StateChange>,
+    ) {
+        let key = (wallet_pubkey.clone(), mint.clone());
+        if let Some(existing) = self.balances.get(&key) {
+            if slot < existing.slot {
+                return;
+            }
+        }
+        let previous = self.balances.insert(
+            key,
+            BalanceState {
+                tokens,
+                slot,
+                token_account: token_account.cloned(),
+            },
+        );
+        if previous.map(|balance| balance.tokens) != Some(tokens) {
+            changes.push(StateChange::BalanceChanged {
+                wallet_pubkey: wallet_pubkey.clone(),
+                mint: mint.clone(),
+                tokens,
+            });
+        }
+
+        for position in self.positions.values_mut() {
+            let matches = match token_account {
+                Some(account) => &position.token_account == account,
+                None => &position.wallet_pubkey == wallet_pubkey && &position.mint == mint,
+            };
+            if matches && slot >= position.slot && position.tokens != tokens {
+                position.tokens = tokens;
+                position.slot = slot;
+                changes.push(StateChange::PositionUpdated {
+                    position_id: position.position_id,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(n: u8) -> Pubkey58 {
+        Pubkey58::from_bytes(&[n; 32])
+    }
+
+    fn opened(position_id: u64, token_account: u8, watched: bool) -> ServerMessage {
+        ServerMessage::PositionOpened {
+            position_id,
+            wallet_pubkey: key(1),
+            mint: key(2),
+            token_account: key(token_account),
+            token_program: None,
+            tokens: 1_000,
+            entry_quote_units: 500,
+            market_context: None,
+            token_name: None,
+            token_symbol: None,
+            token_decimals: None,
+            token_price_quote: Some(10),
+            market_cap_quote: None,
+            pool_liquidity_quote: None,
+            opened_at_ms: None,
+            slot: 5,
+            watched,
+            mirror_source: None,
+        }
+    }
+
+    fn pnl(position_id: u64, profit_units: i64) -> ServerMessage {
+        ServerMessage::PnlUpdate {
+            position_id,
+            profit_units,
+            proceeds_units: 600,
+            server_time_ms: 99,
+            token_price_quote: Some(12),
+            market_cap_quote: None,
+            watched: false,
+        }
+    }
+
+    fn closed(position_id: u64) -> ServerMessage {
+        ServerMessage::PositionClosed {
+            position_id,
+            wallet_pubkey: key(1),
+            mint: key(2),
+            token_account: None,
+            reason: ExitReason::TakeProfit,
+            mirror_source: None,
+            slot: 9,
+            watched: false,
+        }
+    }
+
+    #[test]
+    fn tracks_position_lifecycle() {
+        let mut state = SessionState::new();
+        assert_eq!(
+            state.apply(&opened(1, 3, false)),
+            vec![StateChange::PositionOpened { position_id: 1 }]
+        );
+        state.apply(&opened(2, 4, true));
+
+        assert_eq!(
+            state.apply(&pnl(1, 100)),
+            vec![StateChange::PositionUpdated { position_id: 1 }]
+        );
+        let position = state.position(1).expect("open");
+        assert_eq!(position.profit_units, Some(100));
+        assert_eq!(position.token_price_quote, Some(12));
+        assert_eq!(
+            state
+                .position_by_token_account(&key(4))
+                .map(|p| p.position_id),
+            Some(2)
+        );
+        assert_eq!(state.own_positions().count(), 1);
+        assert_eq!(state.watched_positions().count(), 1);
+
+        let changes = state.apply(&closed(1));
+        assert!(matches!(
+            &changes[..],
+            [StateChange::PositionClosed { position, reason: ExitReason::TakeProfit }]
+                if position.profit_units == Some(100)
+        ));
+        assert!(state.position(1).is_none());
+        assert!(state.position_by_token_account(&key(3)).is_none());
+    }
+
+    #[test]
+    fn positions_can_share_a_token_account() {
+        let mut state = SessionState::new();
+        state.apply(&opened(1, 3, false));
+        state.apply(&opened(2, 3, false));
+        let by_account = |state: &SessionState| {
+            state
+                .position_by_token_account(&key(3))
+                .map(|p| p.position_id)
+        };
+        assert_eq!(by_account(&state), Some(2));
+
+        // Closing the position the lookup does not point at leaves it alone.
+        state.apply(&closed(1));
+        assert_eq!(by_account(&state), Some(2));
+
+        state.apply(&opened(3, 3, false));
+        state.apply(&closed(3));
+        assert_eq!(by_account(&state), Some(2));
+        state.apply(&closed(2));
+        assert_eq!(by_account(&state), None);
+    }
+
+    #[test]
+    fn applies_batches_in_order() {
+        let mut state = SessionState::new();
+        let batch = ServerMessage::Batch {
+            messages: vec![opened(1, 3, false), pnl(1, 100), pnl(1, 150)],
+        };
+        assert_eq!(
+            state.apply(&batch),
+            vec![
+                StateChange::PositionOpened { position_id: 1 },
+                StateChange::PositionUpdated { position_id: 1 },
+                StateChange::PositionUpdated { position_id: 1 },
+            ]
+        );
+        assert_eq!(state.position(1).and_then(|p| p.profit_units), Some(150));
+    }
+
+    #[test]
+    fn applies_pnl_deltas_to_matching_base() {
+        let mut state = SessionState::new();
+        state.apply(&opened(1, 3, false));
+        state.apply(&pnl(1, 100));
+        let delta = |base_server_time_ms| ServerMessage::PnlDelta {
+            position_id: 1,
+            base_server_time_ms,
+            server_time_ms: 150,
+            profit_units: Some(120),
+            proceeds_units: None,
+            token_price_quote: Some(13),
+            market_cap_quote: None,
+        };
+        assert_eq!(
+            state.apply(&delta(50)),
+            vec![StateChange::Inconsistency(
+                Inconsistency::PnlDeltaBaseMismatch {
+                    position_id: 1,
+                    expected: Some(99),
+                    found: 50,
+                }
+            )]
+        );
+        assert_eq!(
+            state.apply(&delta(99)),
+            vec![StateChange::PositionUpdated { position_id: 1 }]
+        );
+        let position = state.position(1).expect("open");
+        assert_eq!(position.profit_units, Some(120));
+        assert_eq!(position.proceeds_units, Some(600));
+        assert_eq!(position.token_price_quote, Some(13));
+        assert_eq!(position.pnl_time_ms, Some(150));
+    }
+
+    #[test]
+    fn reports_inconsistencies() {
+        let mut state = SessionState::new();
+        state.apply(&opened(1, 3, false));
+        assert_eq!(
+            state.apply(&opened(1, 3, false)),
+            vec![StateChange::Inconsistency(Inconsistency::DuplicateOpen {
+                position_id: 1
+            })]
+        );
+        assert_eq!(
+            state.apply(&pnl(7, 1)),
+            vec![StateChange::Inconsistency(Inconsistency::UnknownPosition {
+                position_id: 7,
+                event: "pnl_update",
+            })]
+        );
+        assert_eq!(
+            state.apply(&closed(7)),
+            vec![StateChange::Inconsistency(Inconsistency::UnknownPosition {
+                position_id: 7,
+                event: "position_closed",
+            })]
+        );
+    }
+
+    #[test]
+    fn balance_updates_track_wallets_and_positions() {
+        let mut state = SessionState::new();
+        state.apply(&opened(1, 3, false));
+        let balance = |tokens, slot| ServerMessage::BalanceUpdate {
+            wallet_pubkey: key(1),
+            mint: key(2),
+            token_account: Some(key(3)),
+            token_program: None,
+            tokens,
+            slot,
+        };
+
+        assert_eq!(
+            state.apply(&balance(400, 6)),
+            vec![
+                StateChange::BalanceChanged {
+                    wallet_pubkey: key(1),
+                    mint: key(2),
+                    tokens: 400,
+                },
+                StateChange::PositionUpdated { position_id: 1 },
+            ]
+        );
+        assert_eq!(state.position(1).map(|p| p.tokens), Some(400));
+
+        // Older snapshots are ignored.
+        assert!(state.apply(&balance(900, 4)).is_empty());
+        assert_eq!(state.balance(&key(1), &key(2)).map(|b| b.tokens), Some(400));
+    }
+
+    #[test]
+    fn new_session_drops_positions() {
+        let hello = |session_id| ServerMessage::HelloOk {
+            session_id,
+            server_time_ms: 0,
+            limits: LimitsMsg {
+                hi_capacity: 1,
+                pnl_flush_ms: 100,
+                max_positions_per_session: 1,
+                max_wallets_per_session: 1,
+                max_positions_per_wallet: 1,
+                max_sessions_per_api_key: 1,
+                max_watch_wallets_per_session: 1,
+            },
+            protocol_version: crate::PROTOCOL_VERSION,
+            capabilities: vec![],
+            encoding: None,
+        };
+        let mut state = SessionState::new();
+        state.apply(&hello(1));
+        state.apply(&opened(1, 3, false));
+        assert!(state.apply(&hello(1)).is_empty());
+        assert_eq!(state.positions().count(), 1);
+
+        assert_eq!(
+            state.apply(&hello(2)),
+            vec![StateChange::SessionStarted { session_id: 2 }]
+        );
+        assert_eq!(state.positions().count(), 0);
+        assert_eq!(state.session_id(), Some(2));
+    }
+}
@@ -0,0 +1,821 @@
+//! Evaluation and validation of [`StrategyConfigMsg`].
+//!
+//! [`Evaluator`] follows one position and reports the exits its strategy
+//! would trigger, which lets clients backtest strategies and predict the
+//! server's `ExitSignalWithTx` events.
+//!
+//! PnL is measured in percent of the entry cost: `profit_units /
+//! entry_quote_units * 100` for `PnlUpdate`, and the price change relative to
+//! the entry price for `TradeTick`. Exit rules are applied as follows:
+//!
+//! - Take-profit levels fire in order once PnL reaches `profit_pct`, each
+//!   selling `sell_pct` of the remaining position. Several levels can fire on
+//!   one observation. A non-zero level `trailing_stop_pct` replaces the active
+//!   trailing stop; 0 leaves it unchanged.
+//! - `target_profit_pct` exits the whole position, but only when
+//!   `take_profit_levels` is empty.
+//! - `stop_loss_pct` exits when PnL falls to `-stop_loss_pct`.
+//! - `trailing_stop_pct` exits when PnL falls that many points below its peak,
+//!   once the position has been in profit.
+//! - `breakeven_trail_pct` arms once PnL reaches it and then exits when PnL
+//!   falls back to 0.
+//!
+//! When several stops are crossed at once, the one with the highest trigger
+//! level is reported since it would have fired first. `liquidity_guard` needs
+//! pool depth and is not simulated. 0 disables every percentage threshold.
+//!
+//! [`StrategyConfigMsg::validate`] reports configurations that the rules above
+//! would reject or silently ignore.
+
+use std::fmt;
+
+use crate::{ClientMessage, ErrorCode, ExitReason, ServerMessage, StrategyConfigMsg};
+
+/// How serious a [`StrategyIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The strategy is accepted but likely does not do what was intended.
+    Warning,
+    /// The strategy must be rejected.
+    Error,
+}
+
+/// What is wrong with a strategy field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrategyIssueKind {
+    /// Value is NaN or infinite.
+    NotFinite,
+    /// Percentage is below zero.
+    Negative,
+    /// Percentage is above 100 where that is meaningless.
+    AboveHundred,
+    /// Take-profit level sells nothing.
+    ZeroSellPct,
+    /// Take-profit level does not have a higher `profit_pct` than the previous one.
+    NonMonotonicLevels,
+    /// Take-profit level comes after the position is already fully sold.
+    LevelAfterFullExit,
+    /// `target_profit_pct` is set but ignored because levels are present.
+    TargetProfitIgnored,
+}
+
+impl StrategyIssueKind {
+    /// Human-readable explanation of the issue.
+    pub fn explanation(self) -> &'static str {
+        match self {
+            StrategyIssueKind::NotFinite => "must be a finite number",
+            StrategyIssueKind::Negative => "must not be negative",
+            StrategyIssueKind::AboveHundred => "must not exceed 100",
+            StrategyIssueKind::ZeroSellPct => "level sells nothing when it fires",
+            StrategyIssueKind::NonMonotonicLevels => {
+                "must be greater than the previous level's profit_pct"
+            }
+            StrategyIssueKind::LevelAfterFullExit => {
+                "never fires because an earlier level sells the whole position"
+            }
+            StrategyIssueKind::TargetProfitIgnored => {
+                "ignored because take_profit_levels is non-empty"
+            }
+        }
+    }
+}
+
+/// A single problem found by [`StrategyConfigMsg::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrategyIssue {
+    /// Whether the strategy must be rejected.
+    pub severity: Severity,
+    /// Path of the offending field, e.g. `take_profit_levels[1].profit_pct`.
+    pub field: String,
+    /// What is wrong with the field.
+    pub kind: StrategyIssueKind,
+}
+
+impl StrategyIssue {
+    fn new(severity: Severity, field: impl Into<String>, kind: StrategyIssueKind) -> Self {
+        Self {
+            severity,
+            field: field.into(),
+            kind,
+        }
+    }
+
+    /// Returns true for issues that must reject the strategy.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for StrategyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.kind.explanation())
+    }
+}
+
+impl StrategyConfigMsg {
+    /// Checks the strategy for invalid or ignored settings.
+    ///
+    /// An empty result means the strategy is clean. Any issue with
+    /// [`Severity::Error`] means it should be rejected.
+    pub fn validate(&self) -> Vec<StrategyIssue> {
+        let mut issues = Vec::new();
+        check_pct(
+            &mut issues,
+            "target_profit_pct",
+            self.target_profit_pct,
+            false,
+        );
+        check_pct(&mut issues, "stop_loss_pct", self.stop_loss_pct, true);
+        check_pct(
+            &mut issues,
+            "trailing_stop_pct",
+            self.trailing_stop_pct,
+            true,
+        );
+        check_pct(
+            &mut issues,
+            "breakeven_trail_pct",
+            self.breakeven_trail_pct,
+            false,
+        );
+
+        let mut previous_profit: Option<f64> = None;
+        // `sell_pct` applies to what is left, so track the unsold fraction.
+        let mut remaining = 1.0;
+        for (i, level) in self.take_profit_levels.iter().enumerate() {
+            let field = |name: &str| format!("take_profit_levels[{i}].{name}");
+            check_pct(&mut issues, &field("profit_pct"), level.profit_pct, false);
+            check_pct(&mut issues, &field("sell_pct"), level.sell_pct, true);
+            check_pct(
+                &mut issues,
+                &field("trailing_stop_pct"),
+                level.trailing_stop_pct,
+                true,
+            );
+
+            if remaining <= 0.0 {
+                issues.push(StrategyIssue::new(
+                    Severity::Warning,
+                    format!("take_profit_levels[{i}]"),
+                    StrategyIssueKind::LevelAfterFullExit,
+                ));
+            }
+            if level.sell_pct == 0.0 {
+                issues.push(StrategyIssue::new(
+                    Severity::Warning,
+                    field("sell_pct"),
+                    StrategyIssueKind::ZeroSellPct,
+                ));
+            }
+            if let Some(previous) = previous_profit {
+                if level.profit_pct <= previous {
+                    issues.push(StrategyIssue::new(
+                        Severity::Error,
+                        field("profit_pct"),
+                        StrategyIssueKind::NonMonotonicLevels,
+                    ));
+                }
+            }
+            if level.profit_pct.is_finite() {
+                previous_profit = Some(level.profit_pct);
+            }
+            if (0.0..=100.0).contains(&level.sell_pct) {
+                remaining *= 1.0 - level.sell_pct / 100.0;
+            }
+        }
+
+        if !self.take_profit_levels.is_empty() && self.target_profit_pct != 0.0 {
+            issues.push(StrategyIssue::new(
+                Severity::Warning,
+                "target_profit_pct",
+                StrategyIssueKind::TargetProfitIgnored,
+            ));
+        }
+        issues
+    }
+}
+
+fn check_pct(issues: &mut Vec<StrategyIssue>, field: &str, value: f64, at_most_hundred: bool) {
+    let kind = if !value.is_finite() {
+        StrategyIssueKind::NotFinite
+    } else if value < 0.0 {
+        StrategyIssueKind::Negative
+    } else if at_most_hundred && value > 100.0 {
+        StrategyIssueKind::AboveHundred
+    } else {
+        return;
+    };
+    issues.push(StrategyIssue::new(Severity::Error, field, kind));
+}
+
+impl ClientMessage {
+    /// Returns the strategy carried by `Configure`, `UpdateStrategy` or
+    /// `UpdatePositionStrategy`.
+    pub fn strategy(&self) -> Option<&StrategyConfigMsg> {
+        match self {
+            ClientMessage::Configure { strategy, .. }
+            | ClientMessage::UpdateStrategy { strategy, .. }
+            | ClientMessage::UpdatePositionStrategy { strategy, .. } => Some(strategy),
+            _ => None,
+        }
+    }
+
+    /// Validates the carried strategy, if any.
+    ///
+    /// Field paths are prefixed with `strategy.`.
+    pub fn validate_strategy(&self) -> Vec<StrategyIssue> {
+        let Some(strategy) = self.strategy() else {
+            return Vec::new();
+        };
+        strategy
+            .validate()
+            .into_iter()
+            .map(|mut issue| {
+                issue.field = format!("strategy.{}", issue.field);
+                issue
+            })
+            .collect()
+    }
+
+    /// Builds the `invalid_strategy` error for a command whose strategy has
+    /// error-severity issues.
+    pub fn strategy_error(&self) -> Option<ServerMessage> {
+        let errors: Vec<String> = self
+            .validate_strategy()
+            .iter()
+            .filter(|issue| issue.is_error())
+            .map(ToString::to_string)
+            .collect();
+        if errors.is_empty() {
+            return None;
+        }
+        Some(ServerMessage::Error {
+            code: ErrorCode::InvalidStrategy,
+            message: errors.join("; "),
+            request_id: self.request_id(),
+        })
+    }
+}
+
+/// An exit the strategy would trigger.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exit {
+    /// Position the exit applies to.
+    pub position_id: u64,
+    /// Rule that triggered the exit.
+    pub reason: ExitReason,
+    /// Take-profit level that fired (0-indexed), for chained take profits.
+    pub level_index: Option<u32>,
+    /// Percentage of the remaining position sold by this exit.
+    pub sell_pct: f64,
+    /// PnL percentage observed when the exit fired.
+    pub pnl_pct: f64,
+}
+
+/// Simulates a strategy for a single position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluator {
+    position_id: u64,
+    strategy: StrategyConfigMsg,
+    entry_quote_units: u64,
+    entry_price_quote: Option<u64>,
+    next_level: usize,
+    remaining_pct: f64,
+    peak_pnl_pct: Option<f64>,
+    trailing_stop_pct: f64,
+    breakeven_armed: bool,
+    closed: bool,
+}
+
+impl Evaluator {
+    /// Creates an evaluator for a position with the given entry cost.
+    pub fn new(position_id: u64, entry_quote_units: u64, strategy: StrategyConfigMsg) -> Self {
+        Self {
+            position_id,
+            trailing_stop_pct: strategy.trailing_stop_pct,
+            strategy,
+            entry_quote_units,
+            entry_price_quote: None,
+            next_level: 0,
+            remaining_pct: 100.0,
+            peak_pnl_pct: None,
+            breakeven_armed: false,
+            closed: false,
+        }
+    }
+
+    /// Sets the entry price used to evaluate `TradeTick` prices.
+    pub fn with_entry_price(mut self, price_quote: u64) -> Self {
+        self.entry_price_quote = Some(price_quote);
+        self
+    }
+
+    /// Creates an evaluator from a `PositionOpened` event.
+    ///
+    /// Returns `None` for any other message.
+    pub fn from_position_opened(msg: &ServerMessage, strategy: StrategyConfigMsg) -> Option<Self> {
+        match msg {
+            ServerMessage::PositionOpened {
+                position_id,
+                entry_quote_units,
+                token_price_quote,
+                ..
+            } => {
+                let mut evaluator = Self::new(*position_id, *entry_quote_units, strategy);
+                evaluator.entry_price_quote = *token_price_quote;
+                Some(evaluator)
+            }
+            _ => None,
+        }
+    }
+
+    /// Replaces the strategy, as `UpdatePositionStrategy` does.
+    ///
+    /// Levels that already fired stay consumed and the trailing stop resets
+    /// to the new strategy's `trailing_stop_pct`.
+    pub fn set_strategy(&mut self, strategy: StrategyConfigMsg) {
+        self.trailing_stop_pct = strategy.trailing_stop_pct;
+        self.strategy = strategy;
+    }
+
+    /// Position being evaluated.
+    pub fn position_id(&self) -> u64 {
+        self.position_id
+    }
+
+    /// Active strategy.
+    pub fn strategy(&self) -> &StrategyConfigMsg {
+        &self.strategy
+    }
+
+    /// Percentage of the original position still held.
+    pub fn remaining_pct(&self) -> f64 {
+        self.remaining_pct
+    }
+
+    /// Index of the next take-profit level that can fire.
+    pub fn next_level(&self) -> usize {
+        self.next_level
+    }
+
+    /// Currently active trailing stop percentage (0 = disabled).
+    pub fn trailing_stop_pct(&self) -> f64 {
+        self.trailing_stop_pct
+    }
+
+    /// Highest PnL percentage observed so far.
+    pub fn peak_pnl_pct(&self) -> Option<f64> {
+        self.peak_pnl_pct
+    }
+
+    /// True once the whole position has been exited or closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Feeds a server event; returns the exits it triggers.
+    ///
+    /// `PnlUpdate`, `PnlDelta` and `TradeTick` for this position are
+    /// evaluated and `PositionClosed` stops evaluation. Batches are applied in
+    /// order. Other messages are ignored, as are trade ticks when no entry
+    /// price is known and deltas without a profit change.
+    pub fn apply(&mut self, msg: &ServerMessage) -> Vec<Exit> {
+        match msg {
+            ServerMessage::PnlUpdate {
+                position_id,
+                profit_units,
+                ..
+            }
+            | ServerMessage::PnlDelta {
+                position_id,
+                profit_units: Some(profit_units),
+                ..
+            } if *position_id == self.position_id && self.entry_quote_units > 0 => {
+                let pnl_pct = *profit_units as f64 / self.entry_quote_units as f64 * 100.0;
+                self.observe(pnl_pct)
+            }
+            ServerMessage::TradeTick {
+                position_id,
+                price_quote,
+                ..
+            } if *position_id == self.position_id => match self.entry_price_quote {
+                Some(entry) if entry > 0 => {
+                    let pnl_pct = (*price_quote as f64 - entry as f64) / entry as f64 * 100.0;
+                    self.observe(pnl_pct)
+                }
+                _ => Vec::new(),
+            },
+            ServerMessage::PositionClosed { position_id, .. }
+                if *position_id == self.position_id =>
+            {
+                self.closed = true;
+                Vec::new()
+            }
+            ServerMessage::Batch { messages } => {
+                let mut exits = Vec::new();
+                for msg in messages {
+                    exits.extend(self.apply(msg));
+                }
+                exits
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Feeds a PnL percentage; returns the exits it triggers.
+    pub fn observe(&mut self, pnl_pct: f64) -> Vec<Exit> {
+        let mut exits = Vec::new();
+        if self.closed || !pnl_pct.is_finite() {
+            return exits;
+        }
+        let peak = self.peak_pnl_pct.map_or(pnl_pct, |peak| peak.max(pnl_pct));
+        self.peak_pnl_pct = Some(peak);
+
+        self.take_profits(pnl_pct, &mut exits);
+        if self.closed {
+            return exits;
+        }
+
+        let breakeven = self.strategy.breakeven_trail_pct;
+        if breakeven > 0.0 && peak >= breakeven {
+            self.breakeven_armed = true;
+        }
+
+        let mut stops = Vec::new();
+        if self.strategy.stop_loss_pct > 0.0 {
+            stops.push((-self.strategy.stop_loss_pct, ExitReason::StopLoss));
+        }
+        if self.breakeven_armed {
+            stops.push((0.0, ExitReason::BreakevenTrail));
+        }
+        if self.trailing_stop_pct > 0.0 && peak > 0.0 {
+            stops.push((peak - self.trailing_stop_pct, ExitReason::TrailingStop));
+        }
+        let triggered = stops
+            .into_iter()
+            .filter(|(level, _)| pnl_pct <= *level)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, reason)) = triggered {
+            exits.push(self.exit(reason, None, 100.0, pnl_pct));
+        }
+        exits
+    }
+
+    /// Reports the graduation exit, if `sell_on_graduation` is enabled.
+    pub fn graduate(&mut self) -> Option<Exit> {
+        if self.closed || !self.strategy.sell_on_graduation {
+            return None;
+        }
+        let pnl_pct = self.peak_pnl_pct.unwrap_or(0.0);
+        Some(self.exit(ExitReason::Graduation, None, 100.0, pnl_pct))
+    }
+
+    fn take_profits(&mut self, pnl_pct: f64, exits: &mut Vec<Exit>) {
+        if self.strategy.take_profit_levels.is_empty() {
+            let target = self.strategy.target_profit_pct;
+            if target > 0.0 && pnl_pct >= target {
+                exits.push(self.exit(ExitReason::TakeProfit, None, 100.0, pnl_pct));
+            }
+            return;
+        }
+
+        while let Some(level) = self.strategy.take_profit_levels.get(self.next_level) {
+            if pnl_pct < level.profit_pct {
+                break;
+            }
+            let sell_pct = level.sell_pct.clamp(0.0, 100.0);
+            if level.trailing_stop_pct > 0.0 {
+                self.trailing_stop_pct = level.trailing_stop_pct;
+            }
+            let index = self.next_level as u32;
+            self.next_level += 1;
+            exits.push(self.exit(
+                ExitReason::ChainedTakeProfit,
+                Some(index),
+                sell_pct,
+                pnl_pct,
+            ));
+            if self.closed {
+                return;
+            }
+        }
+    }
+
+    fn exit(
+        &mut self,
+        reason: ExitReason,
+        level_index: Option<u32>,
+        sell_pct: f64,
+        pnl_pct: f64,
+    ) -> Exit {
+        self.remaining_pct *= 1.0 - sell_pct / 100.0;
+        if sell_pct >= 100.0 {
+            self.remaining_pct = 0.0;
+            self.closed = true;
+        }
+        Exit {
+            position_id: self.position_id,
+            reason,
+            level_index,
+            sell_pct,
+            pnl_pct,
+        }
+    }
+}
+
+/// Runs a strategy over a sequence of PnL percentages and collects all exits.
+pub fn simulate(strategy: StrategyConfigMsg, pnl_path: impl IntoIterator<Item = f64>) -> Vec<Exit> {
+    let mut evaluator = Evaluator::new(0, 0, strategy);
+    pnl_path
+        .into_iter()
+        .flat_map(|pnl_pct| evaluator.observe(pnl_pct))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pubkey58, TakeProfitLevelMsg, TradeSide};
+
+    fn level(profit_pct: f64, sell_pct: f64, trailing_stop_pct: f64) -> TakeProfitLevelMsg {
+        TakeProfitLevelMsg {
+            profit_pct,
+            sell_pct,
+            trailing_stop_pct,
+        }
+    }
+
+    fn reasons(exits: &[Exit]) -> Vec<(ExitReason, Option<u32>)> {
+        exits
+            .iter()
+            .map(|exit| (exit.reason.clone(), exit.level_index))
+            .collect()
+    }
+
+    #[test]
+    fn legacy_target_and_stop_loss() {
+        let strategy = StrategyConfigMsg {
+            target_profit_pct: 20.0,
+            stop_loss_pct: 10.0,
+            ..Default::default()
+        };
+        let exits = simulate(strategy.clone(), [5.0, 19.9, 20.0, 30.0]);
+        assert_eq!(reasons(&exits), vec![(ExitReason::TakeProfit, None)]);
+
+        let exits = simulate(strategy, [5.0, -9.0, -10.0]);
+        assert_eq!(reasons(&exits), vec![(ExitReason::StopLoss, None)]);
+        assert_eq!(exits[0].sell_pct, 100.0);
+    }
+
+    #[test]
+    fn levels_override_legacy_target_and_update_trailing() {
+        let strategy = StrategyConfigMsg {
+            target_profit_pct: 5.0,
+            trailing_stop_pct: 20.0,
+            take_profit_levels: vec![level(50.0, 30.0, 10.0), level(100.0, 50.0, 0.0)],
+            ..Default::default()
+        };
+        let mut evaluator = Evaluator::new(1, 1_000, strategy);
+
+        assert!(evaluator.observe(10.0).is_empty());
+        let exits = evaluator.observe(55.0);
+        assert_eq!(
+            reasons(&exits),
+            vec![(ExitReason::ChainedTakeProfit, Some(0))]
+        );
+        assert_eq!(evaluator.trailing_stop_pct(), 10.0);
+        assert!((evaluator.remaining_pct() - 70.0).abs() < 1e-9);
+
+        // Level 1 keeps the trailing stop because its own value is 0.
+        let exits = evaluator.observe(120.0);
+        assert_eq!(
+            reasons(&exits),
+            vec![(ExitReason::ChainedTakeProfit, Some(1))]
+        );
+        assert_eq!(evaluator.trailing_stop_pct(), 10.0);
+        assert!((evaluator.remaining_pct() - 35.0).abs() < 1e-9);
+
+        let exits = evaluator.observe(109.0);
+        assert_eq!(reasons(&exits), vec![(ExitReason::TrailingStop, None)]);
+        assert!(evaluator.is_closed());
+        assert!(evaluator.observe(500.0).is_empty());
+    }
+
+    #[test]
+    fn multiple_levels_fire_on_one_jump() {
+        let strategy = StrategyConfigMsg {
+            take_profit_levels: vec![
+                level(10.0, 25.0, 0.0),
+                level(20.0, 25.0, 0.0),
+                level(30.0, 100.0, 0.0),
+            ],
+            ..Default::default()
+        };
+        let exits = simulate(strategy, [35.0, 40.0]);
+        assert_eq!(
+            reasons(&exits),
+            vec![
+                (ExitReason::ChainedTakeProfit, Some(0)),
+                (ExitReason::ChainedTakeProfit, Some(1)),
+                (ExitReason::ChainedTakeProfit, Some(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn breakeven_trail_takes_precedence_over_lower_stop_loss() {
+        let strategy = StrategyConfigMsg {
+            stop_loss_pct: 10.0,
+            breakeven_trail_pct: 15.0,
+            ..Default::default()
+        };
+        let exits = simulate(strategy.clone(), [16.0, 1.0, -20.0]);
+        assert_eq!(reasons(&exits), vec![(ExitReason::BreakevenTrail, None)]);
+
+        let exits = simulate(strategy, [14.0, -20.0]);
+        assert_eq!(reasons(&exits), vec![(ExitReason::StopLoss, None)]);
+    }
+
+    #[test]
+    fn applies_server_messages_for_its_position() {
+        let opened = ServerMessage::PositionOpened {
+            position_id: 4,
+            wallet_pubkey: Pubkey58::from_bytes(&[1; 32]),
+            mint: Pubkey58::from_bytes(&[2; 32]),
+            token_account: Pubkey58::from_bytes(&[3; 32]),
+            token_program: None,
+            tokens: 1_000,
+            entry_quote_units: 2_000,
+            market_context: None,
+            token_name: None,
+            token_symbol: None,
+            token_decimals: None,
+            token_price_quote: Some(100),
+            market_cap_quote: None,
+            pool_liquidity_quote: None,
+            opened_at_ms: None,
+            slot: 1,
+            watched: false,
+            mirror_source: None,
+        };
+        let strategy = StrategyConfigMsg {
+            target_profit_pct: 50.0,
+            stop_loss_pct: 25.0,
+            sell_on_graduation: true,
+            ..Default::default()
+        };
+        let mut evaluator = Evaluator::from_position_opened(&opened, strategy).expect("opened");
+
+        let pnl = |position_id, profit_units| ServerMessage::PnlUpdate {
+            position_id,
+            profit_units,
+            proceeds_units: 0,
+            server_time_ms: 0,
+            token_price_quote: None,
+            market_cap_quote: None,
+            watched: false,
+        };
+        assert!(evaluator.apply(&pnl(9, -1_000)).is_empty());
+        assert!(evaluator.apply(&pnl(4, -400)).is_empty());
+
+        let tick = ServerMessage::TradeTick {
+            position_id: 4,
+            time_ms: 0,
+            side: TradeSide::Sell,
+            token_amount: 1,
+            quote_amount: 1,
+            price_quote: 70,
+            maker: None,
+            tx_signature: None,
+            watched: false,
+        };
+        let exits = evaluator.apply(&tick);
+        assert_eq!(reasons(&exits), vec![(ExitReason::StopLoss, None)]);
+        assert_eq!(exits[0].position_id, 4);
+        assert!(evaluator.graduate().is_none());
+    }
+
+    fn kinds(issues: &[StrategyIssue]) -> Vec<(Severity, &str, StrategyIssueKind)> {
+        issues
+            .iter()
+            .map(|issue| (issue.severity, issue.field.as_str(), issue.kind))
+            .collect()
+    }
+
+    #[test]
+    fn validate_accepts_clean_strategy() {
+        let strategy = StrategyConfigMsg {
+            stop_loss_pct: 5.0,
+            trailing_stop_pct: 10.0,
+            take_profit_levels: vec![level(50.0, 30.0, 5.0), level(100.0, 70.0, 0.0)],
+            ..Default::default()
+        };
+        assert!(strategy.validate().is_empty());
+    }
+
+    #[test]
+    fn validate_applies_sell_pct_to_the_remaining_position() {
+        let ladder = StrategyConfigMsg {
+            take_profit_levels: vec![
+                level(20.0, 25.0, 0.0),
+                level(50.0, 25.0, 0.0),
+                level(100.0, 100.0, 0.0),
+            ],
+            ..Default::default()
+        };
+        assert!(ladder.validate().is_empty());
+
+        let unreachable = StrategyConfigMsg {
+            take_profit_levels: vec![level(20.0, 100.0, 0.0), level(50.0, 50.0, 0.0)],
+            ..Default::default()
+        };
+        assert_eq!(
+            kinds(&unreachable.validate()),
+            vec![(
+                Severity::Warning,
+                "take_profit_levels[1]",
+                StrategyIssueKind::LevelAfterFullExit
+            )]
+        );
+    }
+
+    #[test]
+    fn validate_reports_invalid_fields() {
+        let strategy = StrategyConfigMsg {
+            target_profit_pct: 25.0,
+            stop_loss_pct: f64::NAN,
+            trailing_stop_pct: -1.0,
+            take_profit_levels: vec![level(50.0, 60.0, 0.0), level(40.0, 50.0, 150.0)],
+            ..Default::default()
+        };
+        assert_eq!(
+            kinds(&strategy.validate()),
+            vec![
+                (
+                    Severity::Error,
+                    "stop_loss_pct",
+                    StrategyIssueKind::NotFinite
+                ),
+                (
+                    Severity::Error,
+                    "trailing_stop_pct",
+                    StrategyIssueKind::Negative
+                ),
+                (
+                    Severity::Error,
+                    "take_profit_levels[1].trailing_stop_pct",
+                    StrategyIssueKind::AboveHundred
+                ),
+                (
+                    Severity::Error,
+                    "take_profit_levels[1].profit_pct",
+                    StrategyIssueKind::NonMonotonicLevels
+                ),
+                (
+                    Severity::Warning,
+                    "target_profit_pct",
+                    StrategyIssueKind::TargetProfitIgnored
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn strategy_error_for_client_commands() {
+        let msg = ClientMessage::UpdatePositionStrategy {
+            position_id: 3,
+            strategy: StrategyConfigMsg {
+                stop_loss_pct: -5.0,
+                ..Default::default()
+            },
+            request_id: Some(11),
+        };
+        assert_eq!(
+            msg.strategy_error(),
+            Some(ServerMessage::Error {
+                code: ErrorCode::InvalidStrategy,
+                message: "strategy.stop_loss_pct: must not be negative".to_string(),
+                request_id: Some(11),
+            })
+        );
+
+        let warning_only = ClientMessage::UpdateStrategy {
+            strategy: StrategyConfigMsg {
+                target_profit_pct: 5.0,
+                take_profit_levels: vec![level(10.0, 50.0, 0.0)],
+                ..Default::default()
+            },
+            request_id: None,
+        };
+        assert_eq!(warning_only.validate_strategy().len(), 1);
+        assert_eq!(warning_only.strategy_error(), None);
+        assert_eq!(
+            ClientMessage::Ping {
+                client_time_ms: 1,
+                request_id: None,
+            }
+            .strategy_error(),
+            None
+        );
+    }
+}
@@ -0,0 +1,728 @@
+//! In-process mock of the stream server (`testing` feature).
+//!
+//! [`MockServer`] speaks the protocol over a loopback WebSocket, for testing
+//! code built on [`StreamClient`](crate::client::StreamClient), or over an
+//! in-memory channel, for code that exchanges messages directly. It answers
+//! the handshake with a configurable `HelloOk`, switching to the encoding it
+//! names, replies `Pong` to `Ping` and
+//! `Ack` to commands carrying a `request_id`, and enforces the `HelloOk`
+//! limits with [`SessionPolicy`]. Tests script server events with
+//! [`MockServer::send`] and assert on what the client sent with
+//! [`MockServer::expect`].
+//!
+//! ```no_run
+//! use lasersell_stream_proto::client::{ClientConfig, StreamClient};
+//! use lasersell_stream_proto::testing::{MockOptions, MockServer};
+//! use lasersell_stream_proto::{ClientMessage, ServerMessage};
+//! # async fn run(configure: ClientMessage, signal: ServerMessage) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut server = MockServer::bind(MockOptions::default()).await?;
+//! let client = StreamClient::connect(ClientConfig::new(server.url(), configure)).await?;
+//! server.send(signal);
+//! let success = server
+//!     .expect(|msg| match msg {
+//!         ClientMessage::MirrorBuyResult { success, .. } => Some(success),
+//!         _ => None,
+//!     })
+//!     .await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::clock::now_ms;
+use crate::policy::SessionPolicy;
+use crate::{ClientMessage, Encoding, ErrorCode, LimitsMsg, ServerMessage, PROTOCOL_VERSION};
+
+/// Behaviour of a [`MockServer`].
+#[derive(Debug, Clone)]
+pub struct MockOptions {
+    /// Reply to the first `Hello` or `Configure` of each connection.
+    ///
+    /// The n-th connection (counting from 0) gets `session_id + n`, so
+    /// reconnects are visible as new sessions.
+    pub hello_ok: ServerMessage,
+    /// How long [`MockServer::recv`] and [`MockServer::expect`] wait.
+    pub recv_timeout: Duration,
+}
+
+impl Default for MockOptions {
+    fn default() -> Self {
+        Self {
+            hello_ok: ServerMessage::HelloOk {
+                session_id: 1,
+                server_time_ms: now_ms(),
+                limits: LimitsMsg {
+                    hi_capacity: 0,
+                    pnl_flush_ms: 1_000,
+                    max_positions_per_session: 0,
+                    max_wallets_per_session: 0,
+                    max_positions_per_wallet: 0,
+                    max_sessions_per_api_key: 0,
+                    max_watch_wallets_per_session: 0,
+                },
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: Vec::new(),
+                encoding: None,
+            },
+            recv_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl MockOptions {
+    /// Replaces the limits announced and enforced by the server.
+    pub fn limits(mut self, new_limits: LimitsMsg) -> Self {
+        if let ServerMessage::HelloOk { limits, .. } = &mut self.hello_ok {
+            *limits = new_limits;
+        }
+        self
+    }
+
+    /// Replaces the whole `HelloOk` reply.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hello_ok` is not a `ServerMessage::HelloOk`, or if it names
+    /// an encoding this build cannot read or write.
+    pub fn hello_ok(mut self, hello_ok: ServerMessage) -> Self {
+        let ServerMessage::HelloOk { encoding, .. } = &hello_ok else {
+            panic!("hello_ok must be a HelloOk message");
+        };
+        if let Some(encoding) = encoding {
+            assert!(
+                encoding.is_supported(),
+                "hello_ok names unsupported encoding {encoding}"
+            );
+        }
+        self.hello_ok = hello_ok;
+        self
+    }
+
+    fn hello_ok_for(&self, connection: usize) -> ServerMessage {
+        let mut msg = self.hello_ok.clone();
+        if let ServerMessage::HelloOk { session_id, .. } = &mut msg {
+            *session_id += connection as u64;
+        }
+        msg
+    }
+
+    fn limits_msg(&self) -> LimitsMsg {
+        match &self.hello_ok {
+            ServerMessage::HelloOk { limits, .. } => limits.clone(),
+            _ => unreachable!("hello_ok is always a HelloOk"),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Control {
+    Send(Box<ServerMessage>),
+    Disconnect,
+}
+
+/// A scripted stream server for tests.
+///
+/// Messages passed to [`MockServer::send`] before a client has completed the
+/// handshake are delivered right after its `HelloOk`. Pings are answered but
+/// not reported by [`MockServer::recv`].
+#[derive(Debug)]
+pub struct MockServer {
+    url: Option<String>,
+    control: mpsc::UnboundedSender<Control>,
+    received: mpsc::UnboundedReceiver<ClientMessage>,
+    connections: Arc<AtomicUsize>,
+    recv_timeout: Duration,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts a server on a loopback WebSocket port.
+    ///
+    /// Connections are served one at a time, so a client can reconnect after
+    /// [`MockServer::disconnect`].
+    pub async fn bind(options: MockOptions) -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("ws://{}", listener.local_addr()?);
+        Ok(Self::start(Some(url), Source::Listener(listener), options))
+    }
+
+    /// Starts a server with one in-memory connection.
+    pub fn in_memory(options: MockOptions) -> (Self, MockConnection) {
+        let (client_tx, client_rx) = mpsc::unbounded_channel();
+        let (server_tx, server_rx) = mpsc::unbounded_channel();
+        let connection = Connection::Channel {
+            incoming: client_rx,
+            outgoing: server_tx,
+        };
+        let server = Self::start(None, Source::Channel(Some(connection)), options);
+        let client = MockConnection {
+            outgoing: client_tx,
+            incoming: server_rx,
+        };
+        (server, client)
+    }
+
+    fn start(url: Option<String>, source: Source, options: MockOptions) -> Self {
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (received_tx, received_rx) = mpsc::unbounded_channel();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let recv_timeout = options.recv_timeout;
+        let server = Server {
+            options,
+            control: control_rx,
+            received: received_tx,
+            connections: Arc::clone(&connections),
+            pending: VecDeque::new(),
+        };
+        Self {
+            url,
+            control: control_tx,
+            received: received_rx,
+            connections,
+            recv_timeout,
+            task: tokio::spawn(server.run(source)),
+        }
+    }
+
+    /// WebSocket URL to connect to.
+    ///
+    /// # Panics
+    ///
+    /// Panics for an in-memory server.
+    pub fn url(&self) -> &str {
+        self.url
+            .as_deref()
+            .expect("in-memory MockServer has no URL")
+    }
+
+    /// Number of connections accepted so far.
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// Sends a message to the connected client.
+    pub fn send(&self, msg: ServerMessage) {
+        let _ = self.control.send(Control::Send(Box::new(msg)));
+    }
+
+    /// Sends messages to the connected client in order.
+    pub fn send_all(&self, msgs: impl IntoIterator<Item = ServerMessage>) {
+        for msg in msgs {
+            self.send(msg);
+        }
+    }
+
+    /// Closes the current connection.
+    pub fn disconnect(&self) {
+        let _ = self.control.send(Control::Disconnect);
+    }
+
+    /// Next message sent by a client, or `None` after the receive timeout.
+    pub async fn recv(&mut self) -> Option<ClientMessage> {
+        time::timeout(self.recv_timeout, self.received.recv())
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Skips client messages until `matcher` accepts one and returns its
+    /// result.
+    ///
+    /// # Panics
+    ///
+    /// Panics, listing the skipped messages, if no message matches within the
+    /// receive timeout.
+    pub async fn expect<T>(&mut self, mut matcher: impl FnMut(ClientMessage) -> Option<T>) -> T {
+        let deadline = time::Instant::now() + self.recv_timeout;
+        let mut skipped = Vec::new();
+        loop {
+            let msg = match time::timeout_at(deadline, self.received.recv()).await {
+                Ok(Some(msg)) => msg,
+                _ => panic!("no matching client message; received {skipped:?}"),
+            };
+            let text = format!("{msg:?}");
+            if let Some(found) = matcher(msg) {
+                return found;
+            }
+            skipped.push(text);
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Client end of an in-memory [`MockServer`] connection.
+#[derive(Debug)]
+pub struct MockConnection {
+    outgoing: mpsc::UnboundedSender<ClientMessage>,
+    incoming: mpsc::UnboundedReceiver<ServerMessage>,
+}
+
+impl MockConnection {
+    /// Sends a command to the server; returns false once the server is gone.
+    pub fn send(&self, msg: ClientMessage) -> bool {
+        self.outgoing.send(msg).is_ok()
+    }
+
+    /// Next message from the server, or `None` once it disconnected.
+    pub async fn recv(&mut self) -> Option<ServerMessage> {
+        self.incoming.recv().await
+    }
+}
+
+/// Pause after a failed `accept` before trying again.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+enum Source {
+    Listener(TcpListener),
+    Channel(Option<Connection>),
+}
+
+impl Source {
+    async fn accept(&mut self) -> Connection {
+        match self {
+            Source::Listener(listener) => loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    // Errors such as EMFILE persist until something changes;
+                    // retrying at once would spin.
+                    time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                };
+                if let Ok(socket) = tokio_tungstenite::accept_async(stream).await {
+                    return Connection::Socket {
+                        socket: Box::new(socket),
+                        encoding: Encoding::Json,
+                    };
+                }
+            },
+            Source::Channel(connection) => match connection.take() {
+                Some(connection) => connection,
+                None => std::future::pending().await,
+            },
+        }
+    }
+}
+
+enum Connection {
+    /// Frames are JSON text until `HelloOk`, then use `encoding`.
+    Socket {
+        socket: Box<WebSocketStream<TcpStream>>,
+        encoding: Encoding,
+    },
+    Channel {
+        incoming: mpsc::UnboundedReceiver<ClientMessage>,
+        outgoing: mpsc::UnboundedSender<ServerMessage>,
+    },
+}
+
+enum Incoming {
+    Message(Box<ClientMessage>),
+    Invalid(String),
+    Closed,
+}
+
+impl Connection {
+    async fn recv(&mut self) -> Incoming {
+        match self {
+            Connection::Socket { socket, encoding } => loop {
+                let decoded = match socket.next().await {
+                    Some(Ok(Message::Text(text))) => {
+                        ClientMessage::from_text(text.as_str()).map_err(|err| err.to_string())
+                    }
+                    Some(Ok(Message::Binary(bytes))) => decode_binary(&bytes, encoding),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Incoming::Closed,
+                    Some(Ok(_)) => continue,
+                };
+                return match decoded {
+                    Ok(msg) => Incoming::Message(Box::new(msg)),
+                    Err(message) => Incoming::Invalid(message),
+                };
+            },
+            Connection::Channel { incoming, .. } => match incoming.recv().await {
+                Some(msg) => Incoming::Message(Box::new(msg)),
+                None => Incoming::Closed,
+            },
+        }
+    }
+
+    async fn send(&mut self, msg: ServerMessage) {
+        match self {
+            Connection::Socket { socket, encoding } => {
+                if let Some(frame) = encode(&msg, encoding) {
+                    let _ = socket.send(frame).await;
+                }
+            }
+            Connection::Channel { outgoing, .. } => {
+                let _ = outgoing.send(msg);
+            }
+        }
+    }
+
+    /// Switches to the encoding a `HelloOk` just sent names.
+    fn negotiated(&mut self, hello_ok: &ServerMessage) {
+        if let (
+            Connection::Socket { encoding, .. },
+            ServerMessage::HelloOk {
+                encoding: Some(chosen),
+                ..
+            },
+        ) = (self, hello_ok)
+        {
+            encoding.clone_from(chosen);
+        }
+    }
+
+    async fn close(self) {
+        if let Connection::Socket { mut socket, .. } = self {
+            let _ = socket.close(None).await;
+        }
+    }
+}
+
+/// Decodes a binary frame with the negotiated encoding; binary frames before
+/// that are JSON.
+fn decode_binary(bytes: &[u8], encoding: &Encoding) -> Result<ClientMessage, String> {
+    match encoding {
+        #[cfg(feature = "msgpack")]
+        Encoding::MessagePack => ClientMessage::decode_binary(bytes).map_err(|err| err.to_string()),
+        #[cfg(feature = "cbor")]
+        Encoding::Cbor => ClientMessage::decode_cbor(bytes).map_err(|err| err.to_string()),
+        _ => serde_json::from_slice(bytes).map_err(|err| err.to_string()),
+    }
+}
+
+/// Encodes a message as JSON text or, once a binary encoding is negotiated,
+/// as a binary frame.
+fn encode(msg: &ServerMessage, encoding: &Encoding) -> Option<Message> {
+    match encoding {
+        #[cfg(feature = "msgpack")]
+        Encoding::MessagePack => msg.encode_binary().ok().map(Message::binary),
+        #[cfg(feature = "cbor")]
+        Encoding::Cbor => msg.encode_cbor().ok().map(Message::binary),
+        _ => msg.to_text().ok().map(Message::text),
+    }
+}
+
+struct Server {
+    options: MockOptions,
+    control: mpsc::UnboundedReceiver<Control>,
+    received: mpsc::UnboundedSender<ClientMessage>,
+    connections: Arc<AtomicUsize>,
+    /// Scripted messages waiting for a client to complete the handshake.
+    pending: VecDeque<ServerMessage>,
+}
+
+impl Server {
+    async fn run(mut self, mut source: Source) {
+        loop {
+            let connection = tokio::select! {
+                connection = source.accept() => connection,
+                control = self.control.recv() => match control {
+                    Some(Control::Send(msg)) => {
+                        self.pending.push_back(*msg);
+                        continue;
+                    }
+                    Some(Control::Disconnect) => continue,
+                    None => return,
+                },
+            };
+            let index = self.connections.fetch_add(1, Ordering::SeqCst);
+            if !self.session(connection, index).await {
+                return;
+            }
+        }
+    }
+
+    /// Serves one connection; returns false once the `MockServer` is gone.
+    async fn session(&mut self, mut connection: Connection, index: usize) -> bool {
+        let mut policy = SessionPolicy::new(self.options.limits_msg());
+        let mut greeted = false;
+        loop {
+            tokio::select! {
+                incoming = connection.recv() => match incoming {
+                    Incoming::Message(msg) => {
+                        if let ClientMessage::Ping { .. } = *msg {
+                            let pong = ServerMessage::Pong { server_time_ms: now_ms() };
+                            connection.send(pong).await;
+                            continue;
+                        }
+                        let _ = self.received.send((*msg).clone());
+                        if let Err(rejection) = policy.apply(&msg) {
+                            connection.send(rejection.into_message()).await;
+                            continue;
+                        }
+                        let handshake = matches!(
+                            *msg,
+                            ClientMessage::Hello { .. } | ClientMessage::Configure { .. }
+                        );
+                        let greeting = handshake && !greeted;
+                        if greeting {
+                            greeted = true;
+                            let hello_ok = self.options.hello_ok_for(index);
+                            connection.send(hello_ok.clone()).await;
+                            connection.negotiated(&hello_ok);
+                        }
+                        if let Some(request_id) = msg.request_id() {
+                            connection.send(ServerMessage::Ack { request_id }).await;
+                        }
+                        if greeting {
+                            while let Some(msg) = self.pending.pop_front() {
+                                connection.send(msg).await;
+                            }
+                        }
+                    }
+                    Incoming::Invalid(message) => {
+                        let error = ServerMessage::Error {
+                            code: ErrorCode::InvalidMessage,
+                            message,
+                            request_id: None,
+                        };
+                        connection.send(error).await;
+                    }
+                    Incoming::Closed => return true,
+                },
+                control = self.control.recv() => match control {
+                    Some(Control::Send(msg)) if greeted => connection.send(*msg).await,
+                    Some(Control::Send(msg)) => self.pending.push_back(*msg),
+                    Some(Control::Disconnect) => {
+                        connection.close().await;
+                        return true;
+                    }
+                    None => {
+                        connection.close().await;
+                        return false;
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt;
+
+    use super::*;
+    use crate::client::{
+        ClientConfig, ReconnectPolicy, ReconnectingClient, StreamClient, StreamEvent,
+    };
+    use crate::fixtures;
+
+    fn configure() -> ClientMessage {
+        let mut msg = fixtures::client_messages()
+            .into_iter()
+            .find(|msg| matches!(msg, ClientMessage::Configure { .. }))
+            .expect("configure fixture");
+        if let ClientMessage::Configure { request_id, .. } = &mut msg {
+            *request_id = None;
+        }
+        msg
+    }
+
+    fn server_message(tag: &str) -> ServerMessage {
+        fixtures::server_messages()
+            .into_iter()
+            .find(|msg| serde_json::to_value(msg).unwrap()["type"] == tag)
+            .unwrap_or_else(|| panic!("no {tag} fixture"))
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[tokio::test]
+    async fn speaks_the_encoding_hello_ok_names() {
+        let mut hello_ok = MockOptions::default().hello_ok;
+        if let ServerMessage::HelloOk { encoding, .. } = &mut hello_ok {
+            *encoding = Some(Encoding::MessagePack);
+        }
+        let mut server = MockServer::bind(MockOptions::default().hello_ok(hello_ok))
+            .await
+            .expect("bind");
+        let mut client = StreamClient::connect(ClientConfig::new(server.url(), configure()))
+            .await
+            .expect("connect");
+        assert_eq!(server.recv().await, Some(configure()));
+
+        let pnl_update = server_message("pnl_update");
+        server.send(pnl_update.clone());
+        assert!(matches!(
+            client.next().await,
+            Some(ServerMessage::HelloOk { .. })
+        ));
+        assert_eq!(client.next().await, Some(pnl_update));
+
+        let ping = ClientMessage::Ping {
+            client_time_ms: 1,
+            request_id: Some(4),
+        };
+        let update = ClientMessage::UpdateWatchWallets {
+            watch_wallets: Vec::new(),
+            request_id: Some(5),
+        };
+        client.send(ping).expect("send");
+        client.send(update.clone()).expect("send");
+        assert_eq!(server.recv().await, Some(update));
+        assert!(matches!(
+            client.next().await,
+            Some(ServerMessage::Pong { .. })
+        ));
+        assert_eq!(
+            client.next().await,
+            Some(ServerMessage::Ack { request_id: 5 })
+        );
+    }
+
+    #[tokio::test]
+    async fn scripts_events_and_asserts_client_replies() {
+        let mut server = MockServer::bind(MockOptions::default())
+            .await
+            .expect("bind");
+        let script = [
+            "position_opened",
+            "pnl_update",
+            "exit_signal_with_tx",
+            "mirror_buy_signal",
+        ]
+        .map(server_message);
+        server.send_all(script.clone());
+
+        let mut client = StreamClient::connect(ClientConfig::new(server.url(), configure()))
+            .await
+            .expect("connect");
+        assert_eq!(server.recv().await, Some(configure()));
+        assert!(matches!(
+            client.next().await,
+            Some(ServerMessage::HelloOk { .. })
+        ));
+        for expected in script {
+            let msg = client.next().await.expect("scripted message");
+            if let ServerMessage::MirrorBuySignal { mint, .. } = &msg {
+                let result = ClientMessage::MirrorBuyResult {
+                    mint: mint.clone(),
+                    success: true,
+                    request_id: Some(11),
+                };
+                client.send(result).expect("send");
+            }
+            assert_eq!(msg, expected);
+        }
+
+        let success = server
+            .expect(|msg| match msg {
+                ClientMessage::MirrorBuyResult { success, .. } => Some(success),
+                _ => None,
+            })
+            .await;
+        assert!(success);
+        assert_eq!(
+            client.next().await,
+            Some(ServerMessage::Ack { request_id: 11 })
+        );
+        client.close().await;
+    }
+
+    #[tokio::test]
+    async fn in_memory_connection_enforces_limits() {
+        let limits = match MockOptions::default().hello_ok {
+            ServerMessage::HelloOk { limits, .. } => LimitsMsg {
+                max_wallets_per_session: 1,
+                ..limits
+            },
+            _ => unreachable!(),
+        };
+        let (mut server, mut client) = MockServer::in_memory(MockOptions::default().limits(limits));
+
+        client.send(ClientMessage::UpdateWallets {
+            wallet_pubkeys: Vec::new(),
+            request_id: Some(1),
+        });
+        assert!(matches!(
+            client.recv().await,
+            Some(ServerMessage::Error {
+                code: ErrorCode::NotConfigured,
+                request_id: Some(1),
+                ..
+            })
+        ));
+
+        // The fixture configures two wallets.
+        client.send(configure());
+        assert!(matches!(
+            client.recv().await,
+            Some(ServerMessage::Error {
+                code: ErrorCode::WalletsPerSessionExceeded,
+                ..
+            })
+        ));
+        let mut single = configure().with_request_id(2);
+        if let ClientMessage::Configure { wallet_pubkeys, .. } = &mut single {
+            wallet_pubkeys.truncate(1);
+        }
+        client.send(single);
+        assert!(matches!(
+            client.recv().await,
+            Some(ServerMessage::HelloOk { session_id: 1, .. })
+        ));
+        assert_eq!(
+            client.recv().await,
+            Some(ServerMessage::Ack { request_id: 2 })
+        );
+
+        client.send(ClientMessage::Ping {
+            client_time_ms: 1,
+            request_id: None,
+        });
+        assert!(matches!(
+            client.recv().await,
+            Some(ServerMessage::Pong { .. })
+        ));
+        assert_eq!(server.connections(), 1);
+        assert!(matches!(
+            server.recv().await,
+            Some(ClientMessage::UpdateWallets { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn disconnect_starts_a_new_session_on_reconnect() {
+        let server = MockServer::bind(MockOptions::default())
+            .await
+            .expect("bind");
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(10),
+            ..ReconnectPolicy::default()
+        };
+        let mut client =
+            ReconnectingClient::connect(ClientConfig::new(server.url(), configure()), policy)
+                .await
+                .expect("connect");
+        assert!(matches!(client.next().await, Some(StreamEvent::Message(_))));
+
+        server.disconnect();
+        assert!(matches!(
+            client.next().await,
+            Some(StreamEvent::Disconnected { .. })
+        ));
+        assert!(matches!(
+            client.next().await,
+            Some(StreamEvent::Reconnected { session_id: 2, .. })
+        ));
+        assert_eq!(server.connections(), 2);
+        client.close().await;
+    }
+}
@@ -0,0 +1,230 @@
+//! TypeScript definitions for the wire protocol (`typescript` feature).
+//!
+//! [`bindings`] renders the serialize-side JSON Schema of every wire type as a
+//! `.d.ts` module: `ClientMessage` and `ServerMessage` become discriminated
+//! unions keyed by `type`, fields skipped when empty become optional
+//! properties, and open enums become string-literal unions that still admit
+//! unknown strings. The checked-in copy lives in
+//! `bindings/lasersell-stream-proto.d.ts` and a test fails when it drifts.
+
+use std::fmt::Write;
+
+use schemars::generate::SchemaSettings;
+use serde_json::Value;
+
+use crate::{ClientMessage, ServerFrame, ServerMessage, PROTOCOL_VERSION};
+
+const INDENT: &str = "  ";
+
+/// Returns the TypeScript definitions of every wire type.
+pub fn bindings() -> String {
+    let mut generator = SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator();
+    generator.subschema_for::<ClientMessage>();
+    generator.subschema_for::<ServerMessage>();
+    generator.subschema_for::<ServerFrame>();
+    let defs = generator.take_definitions(true);
+
+    let mut out = String::new();
+    out.push_str("// Generated by lasersell-stream-proto. Do not edit.\n");
+    out.push_str("// Regenerate with `UPDATE_BINDINGS=1 cargo test --features typescript`.\n\n");
+    let _ = writeln!(
+        out,
+        "/** Protocol version described by these types. */\nexport declare const PROTOCOL_VERSION: {PROTOCOL_VERSION};"
+    );
+    for (name, schema) in &defs {
+        out.push('\n');
+        doc(&mut out, schema, "");
+        if is_object(schema) {
+            let _ = writeln!(out, "export interface {name} {}", object(schema, ""));
+        } else {
+            let _ = writeln!(out, "export type {name} ={};", union_body(schema));
+        }
+    }
+    out
+}
+
+fn is_object(schema: &Value) -> bool {
+    schema["type"] == "object" && schema.get("properties").is_some()
+}
+
+/// Writes the schema's description as a JSDoc comment.
+fn doc(out: &mut String, schema: &Value, indent: &str) {
+    let Some(description) = schema["description"].as_str() else {
+        return;
+    };
+    let description = description.replace("*/", "*\\/");
+    let lines: Vec<&str> = description.lines().collect();
+    if let [line] = lines[..] {
+        let _ = writeln!(out, "{indent}/** {line} */");
+        return;
+    }
+    let _ = writeln!(out, "{indent}/**");
+    for line in lines {
+        let _ = writeln!(
+            out,
+            "{indent} *{}{line}",
+            if line.is_empty() { "" } else { " " }
+        );
+    }
+    let _ = writeln!(out, "{indent} */");
+}
+
+/// Renders the right-hand side of a `type` alias, one union member per line
+/// when the members are objects.
+fn union_body(schema: &Value) -> String {
+    let Some(variants) = variants(schema) else {
+        return format!(" {}", ty(schema, ""));
+    };
+    if !variants.iter().any(is_object) {
+        return format!(" {}", ty(schema, ""));
+    }
+    let mut out = String::new();
+    for variant in variants {
+        out.push('\n');
+        doc(&mut out, variant, INDENT);
+        let _ = write!(out, "{INDENT}| {}", ty(variant, INDENT));
+    }
+    out
+}
+
+fn variants(schema: &Value) -> Option<&Vec<Value>> {
+    schema
+        .get("oneOf")
+        .or_else(|| schema.get("anyOf"))
+        .and_then(Value::as_array)
+}
+
+/// Renders a schema as a TypeScript type expression.
+fn ty(schema: &Value, indent: &str) -> String {
+    if let Some(reference) = schema["$ref"].as_str() {
+        return reference.trim_start_matches("#/$defs/").to_string();
+    }
+    if let Some(value) = schema.get("const") {
+        return value.to_string();
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        return join(values.iter().map(Value::to_string).collect());
+    }
+    if let Some(variants) = variants(schema) {
+        let rendered: Vec<String> = variants.iter().map(|v| ty(v, indent)).collect();
+        let open = rendered.len() > 1 && rendered.iter().any(|t| t == "string");
+        let rendered = rendered
+            .into_iter()
+            .map(|t| {
+                if open && t == "string" {
+                    // Keeps the literals for completion while admitting any string.
+                    "(string & {})".to_string()
+                } else {
+                    t
+                }
+            })
+            .collect();
+        return join(rendered);
+    }
+    match &schema["type"] {
+        Value::Array(types) => join(
+            types
+                .iter()
+                .map(|t| primitive(t.as_str().unwrap_or_default(), schema, indent))
+                .collect(),
+        ),
+        Value::String(t) => primitive(t, schema, indent),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn primitive(name: &str, schema: &Value, indent: &str) -> String {
+    match name {
+        "string" => "string".to_string(),
+        "integer" | "number" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => {
+            let items = ty(&schema["items"], indent);
+            if items.contains(" | ") {
+                format!("({items})[]")
+            } else {
+                format!("{items}[]")
+            }
+        }
+        "object" if schema.get("properties").is_some() => object(schema, indent),
+        "object" => "Record<string, never>".to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+fn object(schema: &Value, indent: &str) -> String {
+    let inner = format!("{indent}{INDENT}");
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let mut out = String::from("{\n");
+    if let Some(properties) = schema["properties"].as_object() {
+        // The discriminator goes first so unions read naturally.
+        let mut names: Vec<&String> = properties.keys().collect();
+        names.sort_by_key(|name| *name != "type");
+        for name in names {
+            let property = &properties[name];
+            doc(&mut out, property, &inner);
+            let mut rendered = ty(property, &inner);
+            let optional = !required.contains(&name.as_str());
+            if optional {
+                // Optional fields are omitted rather than sent as null.
+                if let Some(stripped) = rendered.strip_suffix(" | null") {
+                    rendered = stripped.to_string();
+                }
+            }
+            let _ = writeln!(
+                out,
+                "{inner}{name}{}: {rendered};",
+                if optional { "?" } else { "" }
+            );
+        }
+    }
+    let _ = write!(out, "{indent}}}");
+    out
+}
+
+fn join(mut parts: Vec<String>) -> String {
+    parts.dedup();
+    parts.join(" | ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BINDINGS_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/bindings/lasersell-stream-proto.d.ts"
+    );
+
+    #[test]
+    fn checked_in_bindings_match_rust_types() {
+        let generated = bindings();
+        if std::env::var_os("UPDATE_BINDINGS").is_some() {
+            std::fs::write(BINDINGS_PATH, &generated).expect("write bindings");
+            return;
+        }
+        let checked_in = std::fs::read_to_string(BINDINGS_PATH).unwrap_or_default();
+        assert!(
+            checked_in == generated,
+            "bindings/lasersell-stream-proto.d.ts is out of date; \
+             run `UPDATE_BINDINGS=1 cargo test --features typescript`"
+        );
+    }
+
+    #[test]
+    fn renders_unions_and_optional_fields() {
+        let generated = bindings();
+        assert!(generated.contains(r#"export type TradeSide = "buy" | "sell" | (string & {});"#));
+        assert!(generated.contains("export type ClientMessage =\n"));
+        assert!(generated.contains("    type: \"pnl_update\";\n"));
+        assert!(generated.contains("    token_price_quote?: number;\n"));
+        assert!(generated.contains("    watched?: boolean;\n"));
+        assert!(generated.contains("    send_mode: SendMode | null;\n"));
+    }
+}